@@ -1,56 +1,594 @@
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::models::types::{Platform, QueueId, Region, RiotId};
+use crate::services::account::RiotIdResolver;
+use crate::services::metadata::check_template;
+use crate::services::oauth::{validate_channel_name, AuthFlow, DEFAULT_CHANNEL};
+use crate::services::playlists::PlaylistKind;
+use crate::services::retention::RetentionPolicy;
+use crate::services::riot::RiotClient;
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const DEFAULT_PLATFORM: Platform = Platform::Euw1;
+const DEFAULT_TEMPLATE: &str = "default";
+const QUEUE_KEYS: [&str; 3] = ["soloq", "flex", "other"];
+const MAX_UPLOAD_WORKERS: usize = 8;
 
 #[derive(Debug)]
 pub struct Secrets {
     pub folder_path: PathBuf,
-    pub riot_api_key: String,
-    pub summoner_puuids: Vec<String>,
+    pub accounts: Vec<Account>,
     pub friend_puuids: Vec<String>,
+    pub upload: UploadConfig,
+    pub oauth: OAuthConfig,
+    pub playlists: PlaylistConfig,
+    pub retention: RetentionConfig,
+    pub cleanup: CleanupConfig,
+    pub processes: ProcessConfig,
+    // Modèles de métadonnées par nom ; contient toujours "default"
+    pub templates: HashMap<String, TemplateConfig>,
+    // Noms lisibles (Riot ID) des comptes et amis, indexés par PUUID
+    riot_names: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub puuid: String,
+    pub riot_id: Option<RiotId>,
+    pub platform: Platform,
+    pub region: Region,
+    // Canal YouTube (jeton OAuth2 nommé) vers lequel les parties sont uploadées
+    pub channel: String,
+    // Modèle de métadonnées propre au compte, prioritaire sur celui de la file
+    pub template: Option<String>,
+}
+
+impl Account {
+    /// Identifiant stable du compte : son Riot ID s'il est connu, sinon son PUUID.
+    pub fn name(&self) -> String {
+        match &self.riot_id {
+            Some(riot_id) => riot_id.to_string(),
+            None => self.puuid.clone(),
+        }
+    }
+}
+
+// Compte tel qu'écrit dans la configuration, avant résolution du PUUID
+#[derive(Debug)]
+struct AccountRef {
+    key: AccountKey,
+    platform: Platform,
+    region: Region,
+    channel: String,
+    template: Option<String>,
+}
+
+#[derive(Debug)]
+enum AccountKey {
+    Puuid(String),
+    RiotId(RiotId),
+}
+
+// Contenu brut du fichier de configuration, avant validation
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    folder_path: Option<PathBuf>,
+    riot_api_key: Option<String>,
+    #[serde(default)]
+    accounts: Vec<AccountConfig>,
+    #[serde(default)]
+    friends: Vec<AccountConfig>,
+    #[serde(default)]
+    upload: UploadConfig,
+    #[serde(default)]
+    oauth: OAuthConfig,
+    #[serde(default)]
+    templates: HashMap<String, TemplateConfig>,
+    #[serde(default)]
+    playlists: PlaylistConfig,
+    #[serde(default)]
+    retention: RetentionConfig,
+    #[serde(default)]
+    cleanup: CleanupConfig,
+    #[serde(default)]
+    processes: ProcessConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountConfig {
+    puuid: Option<String>,
+    riot_id: Option<String>,
+    platform: Option<String>,
+    region: Option<String>,
+    channel: Option<String>,
+    template: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadConfig {
+    pub privacy_status: String,
+    pub category_id: String,
+    // Ajoutés aux tags du modèle
+    pub tags: Vec<String>,
+    // Modèle à utiliser par file ("soloq", "flex", "other")
+    pub queue_templates: HashMap<String, String>,
+    // Nombre d'uploads simultanés ; lu uniquement au démarrage
+    pub workers: usize,
+    // Unités de quota YouTube disponibles par jour pour le projet Google
+    pub daily_quota: u32,
+}
+
+/// Modèles du titre, de la description et des tags d'une vidéo. Les variables
+/// s'écrivent entre accolades, ex. `{champion} {kills}/{deaths}/{assists}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateConfig {
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        Self {
+            title: "{champion} {role} {kills}/{deaths}/{assists} - {queue}{with_friends}".to_string(),
+            description: "{result} en {queue} le {date} à {time} (patch {patch}).\nJoueur : {account}".to_string(),
+            tags: vec!["{champion}".to_string(), "{role}".to_string(), "{queue}".to_string()],
+        }
+    }
+}
+
+/// Playlists dans lesquelles ranger chaque vidéo ; désactivé si `by` est vide.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlaylistConfig {
+    pub by: Vec<PlaylistKind>,
+    // Visibilité des playlists créées
+    pub privacy_status: String,
+}
+
+impl Default for PlaylistConfig {
+    fn default() -> Self {
+        Self {
+            by: Vec::new(),
+            privacy_status: "unlisted".to_string(),
+        }
+    }
+}
+
+/// Sort des enregistrements après un upload vérifié.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    pub policy: RetentionPolicy,
+    pub archive_folder: Option<PathBuf>,
+    pub trash_folder: Option<PathBuf>,
+    // Durée de séjour dans la corbeille avant suppression définitive
+    pub trash_days: u32,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            policy: RetentionPolicy::Keep,
+            archive_folder: None,
+            trash_folder: None,
+            trash_days: 7,
+        }
+    }
+}
+
+/// Seuils déclenchant la suppression des enregistrements déjà uploadés ;
+/// désactivé si aucun n'est défini.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CleanupConfig {
+    // Espace libre minimal sur le volume de folder_path
+    pub min_free_gb: Option<f64>,
+    // Taille maximale de l'ensemble des enregistrements de folder_path
+    pub max_folder_gb: Option<f64>,
+}
+
+/// Processus surveillés pour suivre la session de jeu : nom d'exécutable,
+/// `path:<chemin de l'exécutable>` ou `cmd:<extrait de la ligne de commande>`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessConfig {
+    // Processus de la partie elle-même : sa fin déclenche la recherche du match
+    pub game: Vec<String>,
+    pub client: Vec<String>,
+    // Logiciel d'enregistrement : absent pendant une partie, elle ne sera pas enregistrée
+    pub recorder: Vec<String>,
+}
+
+impl Default for ProcessConfig {
+    fn default() -> Self {
+        Self {
+            game: vec!["League of Legends.exe".to_string()],
+            client: vec!["LeagueClient.exe".to_string(), "LeagueClientUx.exe".to_string()],
+            recorder: vec!["Outplayed.exe".to_string()],
+        }
+    }
+}
+
+impl ProcessConfig {
+    /// Tous les motifs à surveiller, sans doublon.
+    pub fn all(&self) -> Vec<String> {
+        let mut patterns: Vec<String> = Vec::new();
+        for pattern in self.game.iter().chain(&self.client).chain(&self.recorder) {
+            if !patterns.contains(pattern) {
+                patterns.push(pattern.clone());
+            }
+        }
+        patterns
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OAuthConfig {
+    pub flow: AuthFlow,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            privacy_status: "unlisted".to_string(),
+            category_id: "20".to_string(), // Gaming
+            tags: Vec::new(),
+            queue_templates: HashMap::new(),
+            workers: 1,
+            daily_quota: 10_000,
+        }
+    }
 }
 
 impl Secrets {
-    pub fn from_env() -> Result<Self, String> {
+    /// Charge la configuration depuis le fichier indiqué par `CONFIG_PATH`
+    /// (`config.toml` par défaut), applique les surcharges du `.env`, puis
+    /// résout les Riot IDs en PUUID.
+    pub async fn load(riot: &RiotClient, resolver: &RiotIdResolver) -> Result<Self, String> {
+        dotenv::dotenv().ok();
+
+        Self::from_file(&Self::config_path(), riot, resolver).await
+    }
+
+    pub fn config_path() -> PathBuf {
+        env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string())
+            .into()
+    }
+
+    pub async fn from_file(
+        path: &Path,
+        riot: &RiotClient,
+        resolver: &RiotIdResolver,
+    ) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Impossible de lire {} : {}", path.display(), e))?;
+        let file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| format!("Configuration invalide dans {} : {}", path.display(), e))?;
+
+        Self::validate(file, riot, resolver).await
+    }
+
+    /// Lit uniquement la section `[oauth]`, sans résoudre les comptes.
+    pub fn load_oauth_config() -> Result<OAuthConfig, String> {
         dotenv::dotenv().ok();
 
+        let path = Self::config_path();
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Impossible de lire {} : {}", path.display(), e))?;
+        let file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| format!("Configuration invalide dans {} : {}", path.display(), e))?;
+        Ok(file.oauth)
+    }
+
+    /// Canaux YouTube utilisés par au moins un compte.
+    pub fn channels(&self) -> Vec<String> {
+        let mut channels: Vec<String> = self.accounts.iter().map(|a| a.channel.clone()).collect();
+        channels.sort();
+        channels.dedup();
+        channels
+    }
+
+    /// Modèle à appliquer à une partie : celui du compte, sinon celui de la
+    /// file, sinon le modèle "default".
+    pub fn template_for(&self, account: &Account, queue: QueueId) -> &TemplateConfig {
+        account
+            .template
+            .as_ref()
+            .or_else(|| self.upload.queue_templates.get(queue.key()))
+            .and_then(|name| self.templates.get(name))
+            .unwrap_or(&self.templates[DEFAULT_TEMPLATE])
+    }
+
+    /// Nom à afficher dans les logs pour un PUUID : son Riot ID s'il est connu.
+    pub fn display_name(&self, puuid: &str) -> String {
+        self.riot_names
+            .get(puuid)
+            .cloned()
+            .unwrap_or_else(|| puuid.to_string())
+    }
+
+    async fn validate(
+        file: ConfigFile,
+        riot: &RiotClient,
+        resolver: &RiotIdResolver,
+    ) -> Result<Self, String> {
+        // Les variables d'environnement ont priorité sur le fichier
         let folder_path = env::var("FOLDER_PATH")
+            .ok()
             .map(PathBuf::from)
-            .map_err(|_| "FOLDER_PATH non défini".to_string())?;
+            .or(file.folder_path)
+            .ok_or("folder_path non défini (ni dans le fichier, ni via FOLDER_PATH)")?;
         println!("folder_path: {:?}", folder_path);
-        let riot_api_key =
-            env::var("RIOT_API_KEY").map_err(|_| "RIOT_API_KEY non défini".to_string())?;
 
-        let summoners_counter: i32 = env::var("SUMMONERS_COUNT")
-            .map_err(|_| "SUMMONERS_COUNT non défini".to_string())?
-            .parse()
-            .map_err(|_| "SUMMONERS_COUNT doit être un nombre entier".to_string())?;
+        let riot_api_key = env::var("RIOT_API_KEY")
+            .ok()
+            .or(file.riot_api_key)
+            .filter(|key| !key.trim().is_empty())
+            .ok_or("riot_api_key non défini (ni dans le fichier, ni via RIOT_API_KEY)")?;
 
-        let mut summoner_puuids: Vec<String> = Vec::new();
+        if file.accounts.is_empty() {
+            return Err("accounts : au moins un compte doit être configuré".to_string());
+        }
 
-        for i in 0..summoners_counter {
-            let summoner_puuid = env::var(format!("SUMMONER_PUUID_{}", i))
-                .map_err(|_| format!("SUMMONER_PUUID_{} non défini", i).to_string())?;
-            summoner_puuids.push(summoner_puuid);
+        check_privacy_status("upload.privacy_status", &file.upload.privacy_status)?;
+        if !(1..=MAX_UPLOAD_WORKERS).contains(&file.upload.workers) {
+            return Err(format!(
+                "upload.workers doit être compris entre 1 et {} (actuellement {})",
+                MAX_UPLOAD_WORKERS, file.upload.workers
+            ));
+        }
+        check_privacy_status("playlists.privacy_status", &file.playlists.privacy_status)?;
+        check_retention(&file.retention, &folder_path)?;
+        if file.processes.game.is_empty() {
+            return Err("processes.game : au moins un processus de jeu doit être configuré".to_string());
+        }
+        let thresholds = [
+            ("cleanup.min_free_gb", file.cleanup.min_free_gb),
+            ("cleanup.max_folder_gb", file.cleanup.max_folder_gb),
+        ];
+        for (field, value) in thresholds {
+            if value.is_some_and(|value| value.is_nan() || value <= 0.0) {
+                return Err(format!("{} doit être strictement positif", field));
+            }
         }
 
-        let friends_counter: i32 = env::var("FRIENDS_COUNT")
-            .map_err(|_| "FRIENDS_COUNT non défini".to_string())?
-            .parse()
-            .map_err(|_| "FRIENDS_COUNT doit être un nombre entier".to_string())?;
+        let mut templates = file.templates;
+        templates.entry(DEFAULT_TEMPLATE.to_string()).or_default();
+        for (name, template) in &templates {
+            check_template(&template.title)
+                .map_err(|e| format!("templates.{}.title : {}", name, e))?;
+            check_template(&template.description)
+                .map_err(|e| format!("templates.{}.description : {}", name, e))?;
+            for (i, tag) in template.tags.iter().enumerate() {
+                check_template(tag).map_err(|e| format!("templates.{}.tags[{}] : {}", name, i, e))?;
+            }
+        }
+        for (queue, name) in &file.upload.queue_templates {
+            if !QUEUE_KEYS.contains(&queue.as_str()) {
+                return Err(format!(
+                    "upload.queue_templates : file inconnue \"{}\" (attendu : {})",
+                    queue,
+                    QUEUE_KEYS.join(", ")
+                ));
+            }
+            if !templates.contains_key(name) {
+                return Err(format!("upload.queue_templates.{} : modèle inconnu \"{}\"", queue, name));
+            }
+        }
 
-        let mut friend_puuids: Vec<String> = Vec::new();
+        let account_refs = file
+            .accounts
+            .into_iter()
+            .enumerate()
+            .map(|(i, a)| parse_account_ref("accounts", i, a))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, account_ref) in account_refs.iter().enumerate() {
+            if let Some(name) = account_ref.template.as_ref().filter(|name| !templates.contains_key(*name)) {
+                return Err(format!("accounts[{}].template : modèle inconnu \"{}\"", i, name));
+            }
+        }
+        let friend_refs = file
+            .friends
+            .into_iter()
+            .enumerate()
+            .map(|(i, f)| parse_account_ref("friends", i, f))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        for i in 0..friends_counter {
-            let friend_puuid = env::var(format!("FRIEND_PUUID_{}", i))
-                .map_err(|_| format!("FRIEND_PUUID_{} non défini", i).to_string())?;
-            friend_puuids.push(friend_puuid);
+        // La résolution des Riot IDs doit utiliser la clé de cette configuration ;
+        // si elle échoue, l'ancienne clé reste en service avec l'ancienne configuration
+        let previous_key = riot.set_api_key(&riot_api_key);
+        let mut riot_names = HashMap::new();
+        let resolved = async {
+            let accounts =
+                resolve_accounts("accounts", account_refs, riot, resolver, &mut riot_names).await?;
+            let friends = resolve_accounts("friends", friend_refs, riot, resolver, &mut riot_names).await?;
+            Ok::<_, String>((accounts, friends))
         }
+        .await;
+        let (accounts, friends) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                riot.set_api_key(&previous_key);
+                return Err(e);
+            }
+        };
+        let friend_puuids = friends.into_iter().map(|f| f.puuid).collect();
 
         Ok(Self {
             folder_path,
-            riot_api_key,
-            summoner_puuids,
+            accounts,
             friend_puuids,
+            upload: file.upload,
+            oauth: file.oauth,
+            playlists: file.playlists,
+            retention: file.retention,
+            cleanup: file.cleanup,
+            processes: file.processes,
+            templates,
+            riot_names,
         })
     }
 }
+
+fn check_privacy_status(field: &str, privacy_status: &str) -> Result<(), String> {
+    if ["public", "unlisted", "private"].contains(&privacy_status) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} invalide : \"{}\" (attendu : public, unlisted ou private)",
+            field, privacy_status
+        ))
+    }
+}
+
+fn check_retention(retention: &RetentionConfig, folder_path: &Path) -> Result<(), String> {
+    let required = match retention.policy {
+        RetentionPolicy::Archive => Some(("archive_folder", &retention.archive_folder)),
+        RetentionPolicy::Trash => Some(("trash_folder", &retention.trash_folder)),
+        RetentionPolicy::Keep | RetentionPolicy::Delete => None,
+    };
+    if let Some((field, None)) = required {
+        return Err(format!("retention.{} est requis par la politique choisie", field));
+    }
+
+    // Un dossier sous folder_path serait à nouveau parcouru à la recherche d'enregistrements
+    let folders = [
+        ("archive_folder", &retention.archive_folder),
+        ("trash_folder", &retention.trash_folder),
+    ];
+    for (field, folder) in folders {
+        if folder.as_ref().is_some_and(|folder| folder.starts_with(folder_path)) {
+            return Err(format!("retention.{} ne doit pas se trouver dans folder_path", field));
+        }
+    }
+    Ok(())
+}
+
+fn parse_account_ref(
+    section: &str,
+    index: usize,
+    account: AccountConfig,
+) -> Result<AccountRef, String> {
+    let key = match (account.puuid, account.riot_id) {
+        (Some(puuid), None) => {
+            let puuid = puuid.trim().to_string();
+            if puuid.is_empty() {
+                return Err(format!("{}[{}].puuid ne peut pas être vide", section, index));
+            }
+            AccountKey::Puuid(puuid)
+        }
+        (None, Some(riot_id)) => RiotId::from_str(&riot_id)
+            .map(AccountKey::RiotId)
+            .map_err(|e| format!("{}[{}].riot_id : {}", section, index, e))?,
+        (Some(_), Some(_)) => {
+            return Err(format!(
+                "{}[{}] : renseigner soit puuid, soit riot_id, pas les deux",
+                section, index
+            ))
+        }
+        (None, None) => {
+            return Err(format!(
+                "{}[{}] : puuid ou riot_id manquant",
+                section, index
+            ))
+        }
+    };
+
+    let platform = match account.platform {
+        Some(platform) => Platform::from_str(&platform)
+            .map_err(|e| format!("{}[{}].platform : {}", section, index, e))?,
+        None => DEFAULT_PLATFORM,
+    };
+    let region = match account.region {
+        Some(region) => {
+            let region = Region::from_str(&region)
+                .map_err(|e| format!("{}[{}].region : {}", section, index, e))?;
+            if region != platform.region() {
+                return Err(format!(
+                    "{}[{}].region : {} ne correspond pas à la plateforme {} (attendu : {})",
+                    section,
+                    index,
+                    region,
+                    platform,
+                    platform.region()
+                ));
+            }
+            region
+        }
+        None => platform.region(),
+    };
+
+    let channel = match account.channel {
+        // Les amis ne sont pas uploadés : un canal n'a pas de sens pour eux
+        Some(_) if section == "friends" => {
+            return Err(format!("{}[{}].channel : réservé aux comptes suivis", section, index))
+        }
+        Some(channel) => {
+            validate_channel_name(&channel).map_err(|e| format!("{}[{}].channel : {}", section, index, e))?;
+            channel
+        }
+        None => DEFAULT_CHANNEL.to_string(),
+    };
+    if account.template.is_some() && section == "friends" {
+        return Err(format!("{}[{}].template : réservé aux comptes suivis", section, index));
+    }
+
+    Ok(AccountRef {
+        key,
+        platform,
+        region,
+        channel,
+        template: account.template,
+    })
+}
+
+async fn resolve_accounts(
+    section: &str,
+    refs: Vec<AccountRef>,
+    riot: &RiotClient,
+    resolver: &RiotIdResolver,
+    riot_names: &mut HashMap<String, String>,
+) -> Result<Vec<Account>, String> {
+    let mut seen = HashSet::new();
+    let mut accounts = Vec::new();
+
+    for (i, account_ref) in refs.into_iter().enumerate() {
+        let (puuid, riot_id) = match account_ref.key {
+            AccountKey::Puuid(puuid) => (puuid, None),
+            AccountKey::RiotId(riot_id) => {
+                let puuid = resolver
+                    .resolve(riot, account_ref.region, &riot_id)
+                    .await
+                    .map_err(|e| format!("{}[{}].riot_id : {}", section, i, e))?;
+                riot_names.insert(puuid.clone(), riot_id.to_string());
+                (puuid, Some(riot_id))
+            }
+        };
+        let account = Account {
+            puuid,
+            riot_id,
+            platform: account_ref.platform,
+            region: account_ref.region,
+            channel: account_ref.channel,
+            template: account_ref.template,
+        };
+        if !seen.insert(account.puuid.clone()) {
+            return Err(format!("{}[{}] est en double : {}", section, i, account.name()));
+        }
+        accounts.push(account);
+    }
+
+    Ok(accounts)
+}
@@ -3,226 +3,834 @@ mod services;
 mod utils;
 pub mod models;
 
-use crate::config::Secrets;
-use crate::services::{api, pid};
-use crate::utils::storage::MatchStorage;
-use std::io::Write;
-use std::path::PathBuf;
-use std::{io, process};
+use crate::config::{Account, Secrets};
+use crate::services::account::RiotIdResolver;
+use crate::services::config_watcher::ConfigWatcher;
+use crate::services::oauth::{self, validate_channel_name, TokenStores};
+use crate::services::riot::{RiotClient, RiotError};
+use crate::services::api::YoutubeError;
+use crate::services::session::GameState;
+use crate::services::pid::{ProcessEvent, ProcessMonitor};
+use crate::services::{api, cleanup, metadata, playlists, recordings, retention};
+use crate::utils::playlist_cache::PlaylistCache;
+use crate::utils::quota::{self, QuotaTracker, PLAYLIST_ITEM_INSERT_COST};
+use crate::utils::redact::redact;
+use crate::utils::storage::{MatchStorage, UploadedRecording};
+use crate::utils::upload_queue::{JobState, UploadJob, UploadQueue};
+use crate::utils::upload_sessions::UploadSessions;
+use std::env;
+use std::pin::pin;
+use std::process;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use oauth2::{basic::BasicClient, reqwest::async_http_client, AuthUrl, ClientId, ClientSecret, TokenUrl};
-use oauth2::{AuthorizationCode, CsrfToken, PkceCodeChallenge, RedirectUrl, RefreshToken, Scope, TokenResponse};
 use tokio::sync::watch;
-use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration};
-use crate::api::{upload_video, delete_video_from_folder};
-use std::fs;
-use serde_json::Value;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use crate::api::upload_video;
+use chrono::{Local, Utc};
+use reqwest::StatusCode;
+
+// Attente maximale d'un worker sans job avant de revérifier la file
+const WORKER_IDLE_DELAY: Duration = Duration::from_secs(60);
+// Délai avant de revérifier une vidéo que YouTube n'a pas fini de traiter
+const PROCESSING_RETRY_DELAY: chrono::Duration = chrono::Duration::minutes(5);
+// Délai avant de retenter un job bloqué par un jeton à réautoriser
+const REAUTHORIZE_RETRY_DELAY: chrono::Duration = chrono::Duration::hours(1);
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// Seule la liste des processus est rafraîchie : un scan fréquent reste peu coûteux
+const PROCESS_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+// Temps laissé aux uploads en cours pour se terminer après une demande d'arrêt ;
+// au-delà, ils reprendront depuis le dernier octet confirmé au redémarrage
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+// Codes de sortie ; un arrêt forcé par un second signal sort avec 128 + son numéro
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_SIGNAL_BASE: i32 = 128;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
 
-    // Génère ou récupère le token d'accès
-    if let Err(e) = get_token().await {
-        eprintln!("Erreur lors de la génération ou récupération du token OAuth2 : {}", e);
-        process::exit(1);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("auth") {
+        process::exit(run_auth_command(&args[1..]).await);
     }
 
-    let secrets = Secrets::from_env().expect("Failed to load secrets");
-    let match_storage = Arc::new(Mutex::new(MatchStorage::new()));
+    let riot = Arc::new(RiotClient::new());
+    let resolver = Arc::new(RiotIdResolver::new());
+    let secrets = Arc::new(Secrets::load(&riot, &resolver).await.expect("Failed to load secrets"));
+
+    // Vérifie le jeton de chaque canal ; un canal sans jeton valide
+    // n'empêche pas les comptes des autres canaux d'être traités
+    let token_stores = Arc::new(TokenStores::new(secrets.oauth.flow));
+    for channel in secrets.channels() {
+        let token_store = token_stores.get(&channel);
+        if let Err(e) = token_store.access_token().await {
+            eprintln!("Canal {} indisponible : {}", channel, redact(&e));
+        } else if token_store.lacks_youtube_scope().await {
+            eprintln!(
+                "Le jeton du canal {} ne permet que l'upload : relancez `auth add {}` pour vérifier les vidéos et gérer les playlists",
+                channel, channel
+            );
+        }
+    }
 
-    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let match_storage = load_or_exit("des matchs traités", MatchStorage::load);
+    let upload_sessions = load_or_exit("des sessions d'upload", UploadSessions::load);
+    let playlist_cache = load_or_exit("du cache des playlists", PlaylistCache::load);
+    let upload_queue = load_or_exit("de la file d'upload", UploadQueue::load);
+    let quota = load_or_exit("du quota YouTube", QuotaTracker::load);
 
-    if !pid::is_process_running("chrome.exe") {
-        println!("chrome.exe non détecté. Le programme va se terminer.");
-        process::exit(1);
-    }
-
-    println!("chrome.exe détecté. Lancement des tâches.");
-
-    let mut handles = vec![];
-    for puuid in &secrets.summoner_puuids {
-        let puuid = puuid.clone();
-        let match_storage = Arc::clone(&match_storage);
-        let folder_path = secrets.folder_path.clone();
-        let api_key = secrets.riot_api_key.clone();
-        let shutdown_rx = shutdown_rx.clone();
-        let friends_puuids = secrets.friend_puuids.clone();
-
-        handles.push(tokio::spawn(async move {
-            let mut is_first_loop = true; // Indique si c'est la première boucle
-            while !*shutdown_rx.borrow() {
-                if let Err(e) =
-                    process_puuid(&puuid, &api_key, &folder_path, &friends_puuids, match_storage.clone(), is_first_loop).await
-                {
-                    eprintln!("Erreur pour le PUUID {}: {}", puuid, e);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    spawn_signal_handler(shutdown_tx);
+    let (config_tx, config_rx) = watch::channel(Arc::clone(&secrets));
+    let (state_tx, state_rx) = watch::channel(GameState::Idle);
+    let context = Context {
+        config_rx,
+        shutdown_rx,
+        state_rx,
+        match_storage: Arc::new(Mutex::new(match_storage)),
+        upload_sessions: Arc::new(Mutex::new(upload_sessions)),
+        playlist_cache: Arc::new(Mutex::new(playlist_cache)),
+        upload_queue: Arc::new(Mutex::new(upload_queue)),
+        upload_notify: Arc::new(Notify::new()),
+        quota: Arc::new(Mutex::new(quota)),
+        resolver,
+        riot,
+        token_stores,
+    };
+
+    let mut tasks: HashMap<String, AccountTask> = HashMap::new();
+    for account in &secrets.accounts {
+        let task = spawn_account_task(account.clone(), None, &context);
+        tasks.insert(account.name(), task);
+    }
+    // Tâches des comptes retirés, encore en train de s'arrêter
+    let mut stopping: HashMap<String, JoinHandle<()>> = HashMap::new();
+
+    let mut workers: Vec<JoinHandle<()>> = (1..=secrets.upload.workers)
+        .map(|worker| spawn_upload_worker(worker, &context))
+        .collect();
+    workers.push(spawn_maintenance(&context));
+
+    let mut config_watcher = match ConfigWatcher::new(&Secrets::config_path()) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("Rechargement à chaud de la configuration désactivé : {}", e);
+            None
+        }
+    };
+
+    // Surveillance des processus dans une tâche dédiée, qui n'émet que les changements
+    let (patterns_tx, patterns_rx) = watch::channel(secrets.processes.all());
+    let (mut process_events, monitor) =
+        ProcessMonitor::new().spawn(patterns_rx, context.shutdown_rx.clone(), PROCESS_SCAN_INTERVAL);
+    workers.push(monitor);
+    let mut running: HashSet<String> = HashSet::new();
+
+    let mut state = GameState::Idle;
+    let mut state_since = Instant::now();
+    let mut client_running = false;
+    println!("En attente d'une partie ({}).", secrets.processes.game.join(", "));
+
+    let mut shutdown_rx = context.shutdown_rx.clone();
+    while !*shutdown_rx.borrow() {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(5)) => {}
+            _ = shutdown_rx.changed() => continue,
+            Some(()) = async { config_watcher.as_mut()?.changed().await } => {
+                reload_config(&config_tx, &mut tasks, &mut stopping, &context).await;
+                let patterns = context.config_rx.borrow().processes.all();
+                patterns_tx.send_if_modified(|current| {
+                    let modified = *current != patterns;
+                    *current = patterns;
+                    modified
+                });
+            }
+            Some(event) = process_events.recv() => match event {
+                ProcessEvent::Started(pattern) => {
+                    running.insert(pattern);
+                }
+                ProcessEvent::Stopped(pattern) => {
+                    running.remove(&pattern);
                 }
-                is_first_loop = false; // Après la première itération, bascule à false
-                sleep(Duration::from_secs(60)).await;
+            },
+        }
+
+        let secrets = Arc::clone(&context.config_rx.borrow());
+        let processes = &secrets.processes;
+        let is_running = |patterns: &[String]| patterns.iter().any(|pattern| running.contains(pattern));
+        let client = is_running(&processes.client);
+        if client != client_running {
+            println!("Client League {}", if client { "lancé" } else { "fermé" });
+            client_running = client;
+        }
+
+        let game_running = is_running(&processes.game);
+        let runnable_uploads = context.upload_queue.lock().await.runnable_jobs();
+        let next = state.next(game_running, runnable_uploads, state_since.elapsed());
+        if next == state {
+            continue;
+        }
+
+        println!("État : {} -> {}", state, next);
+        match next {
+            GameState::InGame if !processes.recorder.is_empty() && !is_running(&processes.recorder) => {
+                eprintln!(
+                    "Aucun logiciel d'enregistrement ({}) ne tourne : cette partie ne sera pas enregistrée",
+                    processes.recorder.join(", ")
+                );
             }
-            println!("Arrêt de la tâche pour le PUUID {}", puuid);
-        }));
+            _ => {}
+        }
+        // Les comptes ne cherchent leurs matchs qu'en après-partie
+        state_tx.send_replace(next);
+        state = next;
+        state_since = Instant::now();
     }
 
-    while pid::is_process_running("chrome.exe") {
-        sleep(Duration::from_secs(5)).await;
+    println!("Arrêt des tâches.");
+    for (_, task) in tasks {
+        let _ = task.handle.await;
     }
-
-    println!("chrome.exe fermé. Arrêt des tâches.");
-    let _ = shutdown_tx.send(true);
-
-    for handle in handles {
+    for (_, handle) in stopping {
         let _ = handle.await;
     }
+    for worker in workers {
+        let _ = worker.await;
+    }
 
+    // Les fichiers d'état sont enregistrés à chaque modification : rien à écrire ici
     println!("Programme terminé.");
-    process::exit(0);
+    process::exit(EXIT_OK);
 }
 
+// Charge un fichier d'état au démarrage ; s'il est illisible, le programme
+// s'arrête plutôt que de repartir d'un état vide qui l'écraserait
+fn load_or_exit<T>(description: &str, load: fn() -> Result<T, String>) -> T {
+    load().unwrap_or_else(|e| {
+        eprintln!("Erreur lors du chargement {} : {}", description, e);
+        process::exit(EXIT_FAILURE);
+    })
+}
 
-async fn process_puuid(
-    puuid: &str,
-    api_key: &str,
-    folder_path: &PathBuf,
-    friends_puuids: &Vec<String>,
-    match_storage: Arc<Mutex<MatchStorage>>,
-    is_first_loop: bool,
-) -> Result<(), String> {
-    let latest_match = api::fetch_latest_match_id(api_key, puuid).await?;
-    upload_video(folder_path).await?;
-    let mut storage = match_storage.lock().await;
-
-    if is_first_loop {
-        // Lors de la première boucle, stocke simplement le match ID
+// Demande l'arrêt au premier signal (Ctrl-C, SIGTERM, fermeture de la console) ;
+// un second signal interrompt le programme sans attendre les uploads
+fn spawn_signal_handler(shutdown_tx: watch::Sender<bool>) {
+    tokio::spawn(async move {
+        let (signal, _) = shutdown_signal().await;
         println!(
-            "Première boucle : enregistrement du match ID pour le PUUID {}",
-            puuid
+            "{} reçu : arrêt en cours, uploads en cours attendus {} s au plus (nouveau signal pour forcer)",
+            signal,
+            SHUTDOWN_GRACE_PERIOD.as_secs()
         );
-        storage.store_match_id(puuid.to_string(), latest_match.to_string());
-        return Ok(());
-    }
-
-    // Vérifie si le match ID a changé
-    if storage.is_match_id_known(puuid, &latest_match) {
-        println!("Match déjà connu pour le PUUID {}", puuid);
-        return Ok(());
-    }
-
-    // Si nouveau match ID, récupère les détails, upload et delete
-    let match_details = api::fetch_match_details(api_key, &latest_match, puuid, friends_puuids).await?;
-    println!("Nouveau match pour le PUUID {}: {:?}", puuid, match_details);
-
-    upload_video(folder_path).await?;
-    delete_video_from_folder("video_path").await?;
-
-    // Met à jour le match ID dans le storage
-    storage.store_match_id(puuid.to_string(), latest_match.to_string());
-
-    Ok(())
-}
-
-async fn get_token() -> Result<String, String> {
-    // Vérifie si un fichier de jetons existe déjà
-    if let Ok(token_content) = fs::read_to_string("token.json") {
-        let token_data: Value = serde_json::from_str(&token_content).map_err(|e| e.to_string())?;
-        if let Some(access_token) = token_data["access_token"].as_str() {
-            return Ok(access_token.to_string());
-        }
-    }
-
-    // Si aucun jeton valide, demande une nouvelle autorisation
-    println!("Génération d'un nouveau jeton OAuth2.");
-    generate_tokens().await?;
-    let token_content = fs::read_to_string("token.json").map_err(|e| e.to_string())?;
-    Ok(token_content)
-}
-
-async fn generate_tokens() -> Result<(), String> {
-    // Chargez le fichier client_secret.json
-    let client_secret = fs::read_to_string("client_secret.json")
-        .map_err(|e| format!("Erreur lors de la lecture de client_secret.json : {}", e))?;
-    let client_info: Value = serde_json::from_str(&client_secret)
-        .map_err(|e| format!("Erreur de parsing JSON : {}", e))?;
-
-    // Configurez le client OAuth2
-    let client_id = ClientId::new(
-        client_info["installed"]["client_id"]
-            .as_str()
-            .ok_or("client_id manquant")?
-            .to_string(),
-    );
-    let client_secret = ClientSecret::new(
-        client_info["installed"]["client_secret"]
-            .as_str()
-            .ok_or("client_secret manquant")?
-            .to_string(),
-    );
-    let auth_uri = AuthUrl::new(
-        client_info["installed"]["auth_uri"]
-            .as_str()
-            .ok_or("auth_uri manquant")?
-            .to_string(),
-    )
-    .unwrap();
-    let token_uri = TokenUrl::new(
-        client_info["installed"]["token_uri"]
-            .as_str()
-            .ok_or("token_uri manquant")?
-            .to_string(),
-    )
-    .unwrap();
-    let redirect_uri = RedirectUrl::new("urn:ietf:wg:oauth:2.0:oob".to_string()).unwrap();
-
-    let client = BasicClient::new(client_id, Some(client_secret), auth_uri, Some(token_uri))
-        .set_redirect_uri(redirect_uri);
-
-    // Générer l'URL d'autorisation
-    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
-    let (auth_url, _csrf_token) = client
-        .authorize_url(CsrfToken::new_random)
-        .set_pkce_challenge(pkce_challenge)
-        .add_scope(Scope::new("https://www.googleapis.com/auth/youtube.upload".to_string()))
-        .url();
-
-    println!("Ouvrez ce lien dans votre navigateur et autorisez l'accès :");
-    println!("{}", auth_url);
-
-    // Recevoir le code d'autorisation de l'utilisateur
-    print!("Entrez le code d'autorisation ici : ");
-    io::stdout().flush().unwrap();
-
-    let mut auth_code = String::new();
-    io::stdin()
-        .read_line(&mut auth_code)
-        .map_err(|e| format!("Erreur de lecture de l'entrée utilisateur : {}", e))?;
-    let auth_code = AuthorizationCode::new(auth_code.trim().to_string());
-
-    // Échanger le code d'autorisation contre les jetons
-    let token_result = client
-        .exchange_code(auth_code)
-        .set_pkce_verifier(pkce_verifier)
-        .request_async(async_http_client)
-        .await
-        .map_err(|e| format!("Erreur lors de l'échange du code : {}", e))?;
+        let _ = shutdown_tx.send(true);
+
+        let (signal, number) = shutdown_signal().await;
+        eprintln!("{} reçu à nouveau : arrêt immédiat", signal);
+        process::exit(EXIT_SIGNAL_BASE + number);
+    });
+}
+
+// Nom et numéro du signal reçu
+#[cfg(unix)]
+async fn shutdown_signal() -> (&'static str, i32) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => tokio::select! {
+            _ = tokio::signal::ctrl_c() => ("SIGINT", SIGINT),
+            _ = sigterm.recv() => ("SIGTERM", SIGTERM),
+        },
+        Err(e) => {
+            eprintln!("Impossible d'écouter SIGTERM : {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            ("SIGINT", SIGINT)
+        }
+    }
+}
+
+// Nom de l'événement reçu et numéro du signal équivalent de la CRT Windows
+#[cfg(windows)]
+async fn shutdown_signal() -> (&'static str, i32) {
+    use tokio::signal::windows::{ctrl_break, ctrl_close, ctrl_shutdown};
+
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+    const SIGBREAK: i32 = 21;
+    // Windows ne laisse que quelques secondes après la fermeture de la console
+    match (ctrl_break(), ctrl_close(), ctrl_shutdown()) {
+        (Ok(mut ctrl_break), Ok(mut ctrl_close), Ok(mut ctrl_shutdown)) => tokio::select! {
+            _ = tokio::signal::ctrl_c() => ("Ctrl-C", SIGINT),
+            _ = ctrl_break.recv() => ("Ctrl-Break", SIGBREAK),
+            _ = ctrl_close.recv() => ("Fermeture de la console", SIGTERM),
+            _ = ctrl_shutdown.recv() => ("Arrêt du système", SIGTERM),
+        },
+        _ => {
+            eprintln!("Impossible d'écouter les événements de la console");
+            let _ = tokio::signal::ctrl_c().await;
+            ("Ctrl-C", SIGINT)
+        }
+    }
+}
+
+// État partagé entre la boucle principale et les tâches des comptes
+#[derive(Clone)]
+struct Context {
+    config_rx: watch::Receiver<Arc<Secrets>>,
+    shutdown_rx: watch::Receiver<bool>,
+    // État de la session de jeu : la recherche des matchs n'a lieu qu'en après-partie
+    state_rx: watch::Receiver<GameState>,
+    match_storage: Arc<Mutex<MatchStorage>>,
+    upload_sessions: Arc<Mutex<UploadSessions>>,
+    playlist_cache: Arc<Mutex<PlaylistCache>>,
+    upload_queue: Arc<Mutex<UploadQueue>>,
+    // Réveille un worker quand un job est ajouté à la file
+    upload_notify: Arc<Notify>,
+    quota: Arc<Mutex<QuotaTracker>>,
+    resolver: Arc<RiotIdResolver>,
+    riot: Arc<RiotClient>,
+    token_stores: Arc<TokenStores>,
+}
+
+// `auth add <canal>` : crée ou remplace le jeton OAuth2 d'un canal YouTube
+async fn run_auth_command(args: &[String]) -> i32 {
+    let channel = match args {
+        [command, channel] if command == "add" => channel,
+        _ => {
+            eprintln!("Usage : auth add <canal>");
+            return 2;
+        }
+    };
+    if let Err(e) = validate_channel_name(channel) {
+        eprintln!("Erreur : {}", e);
+        return 2;
+    }
+
+    let oauth = match Secrets::load_oauth_config() {
+        Ok(oauth) => oauth,
+        Err(e) => {
+            eprintln!("Erreur lors du chargement de la configuration : {}", redact(&e));
+            return 1;
+        }
+    };
+
+    match TokenStores::new(oauth.flow).get(channel).authorize_new().await {
+        Ok(()) => {
+            println!("Canal {} autorisé.", channel);
+            0
+        }
+        Err(e) => {
+            eprintln!("Erreur lors de l'autorisation du canal {} : {}", channel, redact(&e));
+            1
+        }
+    }
+}
+
+struct AccountTask {
+    // Compte tel que configuré au lancement de la tâche
+    account: Account,
+    stop_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+// `previous` est la tâche arrêtée que celle-ci remplace : on attend sa fin
+// pour ne jamais traiter le même compte deux fois en parallèle
+fn spawn_account_task(account: Account, previous: Option<JoinHandle<()>>, context: &Context) -> AccountTask {
+    let (stop_tx, mut stop_rx) = watch::channel(false);
+    let mut context = context.clone();
+    let name = account.name();
+    let configured = account.clone();
+    let mut account = account;
+
+    let handle = tokio::spawn(async move {
+        if let Some(previous) = previous {
+            let _ = previous.await;
+        }
+        // Au lancement de la tâche, rattrape les matchs joués entre-temps, jusqu'à
+        // ce que le compte soit à jour ; ensuite, ne cherche qu'en après-partie
+        let mut catching_up = true;
+        while !*context.shutdown_rx.borrow() && !*stop_rx.borrow() {
+            let polling = catching_up || *context.state_rx.borrow_and_update() == GameState::PostGame;
+            if !polling {
+                tokio::select! {
+                    _ = context.state_rx.changed() => {}
+                    _ = context.shutdown_rx.changed() => {}
+                    _ = stop_rx.changed() => {}
+                }
+                continue;
+            }
+
+            // Relit la configuration à chaque tour pour prendre en compte les modifications
+            let secrets = Arc::clone(&context.config_rx.borrow());
+            match process_puuid(&account, &secrets, &context).await {
+                Ok(PollOutcome::UpToDate) => catching_up = false,
+                Ok(PollOutcome::RecordingPending) => {}
+                Err(PollError::Other(e)) => eprintln!("Erreur pour {}: {}", name, redact(&e)),
+                Err(PollError::InvalidPuuid(e)) => {
+                    eprintln!("Erreur pour {}: {}", name, redact(&e));
+                    // Un PUUID en cache peut être devenu invalide (ex. changement de clé API) :
+                    // on redemande le PUUID correspondant au Riot ID
+                    if let Some(riot_id) = &account.riot_id {
+                        match context.resolver.refresh(&context.riot, account.region, riot_id).await {
+                            Ok(new_puuid) if new_puuid != account.puuid => {
+                                println!("PUUID de {} mis à jour", name);
+                                account.puuid = new_puuid;
+                            }
+                            Ok(_) => {}
+                            Err(e) => eprintln!("Impossible de résoudre à nouveau {}: {}", name, redact(&e)),
+                        }
+                    }
+                }
+            }
+            tokio::select! {
+                _ = sleep(Duration::from_secs(60)) => {}
+                _ = context.state_rx.changed() => {}
+                _ = context.shutdown_rx.changed() => {}
+                _ = stop_rx.changed() => {}
+            }
+        }
+        println!("Arrêt de la tâche pour {}", name);
+    });
+
+    AccountTask {
+        account: configured,
+        stop_tx,
+        handle,
+    }
+}
 
-    println!("Jeton d'accès : {}", token_result.access_token().secret());
-    if let Some(refresh_token) = token_result.refresh_token() {
-        println!("Jeton d'actualisation : {}", refresh_token.secret());
-    } else {
-        println!("Aucun jeton d'actualisation reçu !");
+fn spawn_upload_worker(worker: usize, context: &Context) -> JoinHandle<()> {
+    let context = context.clone();
+    let mut shutdown_rx = context.shutdown_rx.clone();
+
+    tokio::spawn(async move {
+        while !*shutdown_rx.borrow() {
+            let claimed = context.upload_queue.lock().await.claim_next();
+            let job = match claimed {
+                Ok(Some(job)) => job,
+                Ok(None) => {
+                    // Rien à faire : attend un nouveau job ou le prochain nouvel essai
+                    let retry_at = context.upload_queue.lock().await.next_retry_at();
+                    let idle = retry_at
+                        .map(|at| (at - Utc::now()).to_std().unwrap_or_default())
+                        .map_or(WORKER_IDLE_DELAY, |delay| delay.min(WORKER_IDLE_DELAY));
+                    tokio::select! {
+                        _ = context.upload_notify.notified() => {}
+                        _ = sleep(idle) => {}
+                        _ = shutdown_rx.changed() => {}
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Worker {} : erreur de la file d'upload : {}", worker, e);
+                    tokio::select! {
+                        _ = sleep(WORKER_IDLE_DELAY) => {}
+                        _ = shutdown_rx.changed() => {}
+                    }
+                    continue;
+                }
+            };
+
+            println!("Worker {} : traitement du match {} pour {}", worker, job.match_id, job.account);
+            let secrets = Arc::clone(&context.config_rx.borrow());
+            // À l'arrêt, l'upload dispose d'un délai de grâce pour se terminer ; passé ce
+            // délai il est interrompu et sa session permet de le reprendre au redémarrage
+            let mut upload = pin!(run_upload_job(&job, &secrets, &context));
+            let result = tokio::select! {
+                result = &mut upload => Some(result),
+                _ = shutdown_rx.changed() => {
+                    let result = timeout(SHUTDOWN_GRACE_PERIOD, &mut upload).await.ok();
+                    if result.is_none() {
+                        println!(
+                            "Worker {} : upload du match {} interrompu, il reprendra au prochain démarrage",
+                            worker, job.match_id
+                        );
+                    }
+                    result
+                }
+            };
+
+            let mut queue = context.upload_queue.lock().await;
+            let saved = match result {
+                None => queue.release(&job.id),
+                Some(Ok(())) => {
+                    println!("Worker {} : match {} terminé", worker, job.match_id);
+                    queue.mark_done(&job.id)
+                }
+                Some(Err(YoutubeError::QuotaExceeded)) => {
+                    let reset = quota::next_reset(Utc::now());
+                    println!(
+                        "Worker {} : quota YouTube épuisé, match {} reporté à {}",
+                        worker,
+                        job.match_id,
+                        reset.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+                    );
+                    queue.defer(&job.id, reset)
+                }
+                Some(Err(YoutubeError::InsufficientScope)) => {
+                    // Sans nouveau consentement, chaque essai échouerait : le job
+                    // attend sans consommer ses tentatives
+                    eprintln!(
+                        "Worker {} : le jeton du canal {} n'autorise pas l'accès complet à YouTube, relancez `auth add {}` (match {} reporté)",
+                        worker, job.channel, job.channel, job.match_id
+                    );
+                    queue.defer(&job.id, Utc::now() + REAUTHORIZE_RETRY_DELAY)
+                }
+                Some(Err(YoutubeError::Processing)) => {
+                    println!(
+                        "Worker {} : vidéo du match {} en cours de traitement par YouTube, nouvelle vérification plus tard",
+                        worker, job.match_id
+                    );
+                    queue
+                        .defer_processing(&job.id, Utc::now() + PROCESSING_RETRY_DELAY)
+                        .map(|state| {
+                            if state == JobState::Failed {
+                                eprintln!(
+                                    "Match {} abandonné : vidéo toujours en traitement par YouTube",
+                                    job.match_id
+                                );
+                            }
+                        })
+                }
+                Some(Err(YoutubeError::Other(e))) => {
+                    let e = redact(&e);
+                    eprintln!("Worker {} : échec pour le match {} : {}", worker, job.match_id, e);
+                    queue.mark_failed(&job.id, e).map(|state| {
+                        if state == JobState::Failed {
+                            eprintln!("Match {} abandonné après plusieurs échecs", job.match_id);
+                        }
+                    })
+                }
+            };
+            if let Err(e) = saved {
+                eprintln!("Worker {} : impossible d'enregistrer la file d'upload : {}", worker, e);
+            }
+        }
+        println!("Arrêt du worker d'upload {}", worker);
+    })
+}
+
+async fn run_upload_job(job: &UploadJob, secrets: &Secrets, context: &Context) -> Result<(), YoutubeError> {
+    let token_store = context.token_stores.get(&job.channel);
+    let video_id = match &job.video_id {
+        Some(video_id) => video_id.clone(),
+        None => {
+            println!("Titre de la vidéo : {}", job.video.title);
+            let video_id = upload_video(
+                &job.file,
+                &job.video,
+                &secrets.upload,
+                &token_store,
+                &context.upload_sessions,
+                &context.quota,
+            )
+            .await?;
+            context
+                .upload_queue
+                .lock()
+                .await
+                .mark_uploaded(&job.id, video_id.clone())?;
+            video_id
+        }
+    };
+
+    // La vidéo est en ligne : un échec ici ne doit pas provoquer un nouvel upload
+    let missing = job.missing_playlists();
+    if !missing.is_empty() {
+        // Sans quota pour tous les ajouts, le job est reporté avant d'en faire aucun
+        let cost = PLAYLIST_ITEM_INSERT_COST * missing.len() as u32;
+        let project = oauth::google_project()?;
+        if !context
+            .quota
+            .lock()
+            .await
+            .try_reserve(&project, cost, secrets.upload.daily_quota)?
+        {
+            return Err(YoutubeError::QuotaExceeded);
+        }
+        let mut added = Vec::new();
+        let result = playlists::add_to_playlists(
+            &video_id,
+            &missing,
+            &secrets.playlists,
+            &token_store,
+            &context.playlist_cache,
+            &context.quota,
+            &mut added,
+        )
+        .await;
+        // Un nouvel essai ne doit ni dupliquer les ajouts réussis ni consommer
+        // à nouveau leur quota
+        context
+            .upload_queue
+            .lock()
+            .await
+            .mark_added_to_playlists(&job.id, added)?;
+        result?;
     }
 
-    // Sauvegarder les jetons dans un fichier
-    let token_json = serde_json::to_string_pretty(&token_result)
-        .map_err(|e| format!("Erreur lors de la sérialisation des jetons : {}", e))?;
-    fs::write("token.json", token_json)
-        .map_err(|e| format!("Erreur lors de l'écriture dans tokens.json : {}", e))?;
+    // L'enregistrement n'est touché qu'une fois la vidéo confirmée par YouTube
+    api::verify_upload(&video_id, &token_store, &context.quota).await?;
+    context.match_storage.lock().await.record_upload(
+        job.puuid.clone(),
+        UploadedRecording {
+            match_id: job.match_id.clone(),
+            file: job.file.clone(),
+            video_id: video_id.clone(),
+            uploaded_at: Utc::now(),
+        },
+    )?;
+    Ok(retention::apply_retention(&job.file, &secrets.retention, &job.match_id, &video_id).await?)
+}
+
+// Vide régulièrement la corbeille et libère de l'espace disque si nécessaire
+fn spawn_maintenance(context: &Context) -> JoinHandle<()> {
+    let context = context.clone();
+    let mut shutdown_rx = context.shutdown_rx.clone();
 
-    println!("Jetons sauvegardés dans tokens.json");
-    Ok(())
+    tokio::spawn(async move {
+        while !*shutdown_rx.borrow() {
+            let secrets = Arc::clone(&context.config_rx.borrow());
+            if let Err(e) = retention::purge_trash(&secrets.retention).await {
+                eprintln!("Erreur lors du nettoyage de la corbeille : {}", e);
+            }
+            if let Err(e) =
+                cleanup::free_disk_space(&secrets.folder_path, &secrets.cleanup, &context.match_storage).await
+            {
+                eprintln!("Erreur lors du nettoyage de l'espace disque : {}", e);
+            }
+            tokio::select! {
+                _ = sleep(MAINTENANCE_INTERVAL) => {}
+                _ = shutdown_rx.changed() => {}
+            }
+        }
+    })
 }
 
-fn save_token(token: &str) -> Result<(), String> {
-    fs::write("token.json", token).map_err(|e| format!("Erreur d'écriture des jetons : {}", e))
-}
\ No newline at end of file
+// Recharge le fichier de configuration et démarre/arrête les tâches des comptes
+// ajoutés ou retirés. Un compte modifié (canal, modèle, plateforme...) voit sa
+// tâche relancée avec les nouveaux paramètres.
+async fn reload_config(
+    config_tx: &watch::Sender<Arc<Secrets>>,
+    tasks: &mut HashMap<String, AccountTask>,
+    stopping: &mut HashMap<String, JoinHandle<()>>,
+    context: &Context,
+) {
+    let secrets = match Secrets::load(&context.riot, &context.resolver).await {
+        Ok(secrets) => Arc::new(secrets),
+        Err(e) => {
+            eprintln!(
+                "Configuration modifiée mais invalide, ancienne configuration conservée : {}",
+                redact(&e)
+            );
+            return;
+        }
+    };
+    println!("Configuration rechargée.");
+    config_tx.send_replace(Arc::clone(&secrets));
+
+    let names: Vec<String> = secrets.accounts.iter().map(Account::name).collect();
+    let removed: Vec<String> = tasks
+        .keys()
+        .filter(|name| !names.contains(name))
+        .cloned()
+        .collect();
+    stopping.retain(|_, handle| !handle.is_finished());
+    for name in removed {
+        if let Some(task) = tasks.remove(&name) {
+            println!("Compte retiré de la configuration : {}", name);
+            let _ = task.stop_tx.send(true);
+            stopping.insert(name, task.handle);
+        }
+    }
+
+    for account in &secrets.accounts {
+        match tasks.entry(account.name()) {
+            Entry::Vacant(entry) => {
+                println!("Nouveau compte dans la configuration : {}", entry.key());
+                // Un compte retiré puis remis attend la fin de son ancienne tâche
+                let previous = stopping.remove(entry.key());
+                entry.insert(spawn_account_task(account.clone(), previous, context));
+            }
+            Entry::Occupied(entry) if entry.get().account != *account => {
+                println!("Compte modifié dans la configuration : {}", entry.key());
+                let (name, previous) = entry.remove_entry();
+                let _ = previous.stop_tx.send(true);
+                tasks.insert(name, spawn_account_task(account.clone(), Some(previous.handle), context));
+            }
+            Entry::Occupied(_) => {}
+        }
+    }
+}
+
+// Résultat d'un passage sur un compte
+enum PollOutcome {
+    UpToDate,
+    // Un match attend encore son enregistrement : il sera repris au prochain passage
+    RecordingPending,
+}
+
+// Échec d'un passage sur un compte
+enum PollError {
+    // match-v5 refuse le PUUID : il doit être résolu à nouveau depuis le Riot ID
+    InvalidPuuid(String),
+    Other(String),
+}
+
+impl From<String> for PollError {
+    fn from(e: String) -> Self {
+        PollError::Other(e)
+    }
+}
+
+impl From<RiotError> for PollError {
+    fn from(e: RiotError) -> Self {
+        PollError::Other(e.to_string())
+    }
+}
+
+// Erreur d'une requête by-puuid : seuls 400 et 404 mettent en cause le PUUID
+fn by_puuid_error(e: RiotError) -> PollError {
+    match e {
+        RiotError::Rejected { status, message }
+            if status == StatusCode::BAD_REQUEST || status == StatusCode::NOT_FOUND =>
+        {
+            PollError::InvalidPuuid(message)
+        }
+        e => e.into(),
+    }
+}
+
+async fn process_puuid(
+    account: &Account,
+    secrets: &Secrets,
+    context: &Context,
+) -> Result<PollOutcome, PollError> {
+    let puuid = account.puuid.as_str();
+    let name = secrets.display_name(puuid);
+    let last_match = context.match_storage.lock().await.last_match(puuid).cloned();
+
+    let last_match = match last_match {
+        Some(last_match) => last_match,
+        None => {
+            // Compte jamais suivi : son dernier match sert de point de départ,
+            // l'historique antérieur n'est pas uploadé
+            let latest_match =
+                api::fetch_latest_match_id(&context.riot, account.region, account.platform, puuid)
+                    .await
+                    .map_err(by_puuid_error)?;
+            let match_dto = api::fetch_match(&context.riot, account.region, &latest_match).await?;
+            println!("Nouveau compte suivi : enregistrement du match ID pour {}", name);
+            context.match_storage.lock().await.store_baseline(
+                puuid.to_string(),
+                latest_match,
+                match_dto.info.game_start_timestamp / 1000,
+            )?;
+            return Ok(PollOutcome::UpToDate);
+        }
+    };
+
+    // Récupère tous les matchs joués depuis le dernier match traité
+    let match_ids = api::fetch_match_ids_since(
+        &context.riot,
+        account.region,
+        account.platform,
+        puuid,
+        last_match.game_start,
+        &last_match.match_id,
+    )
+    .await
+    .map_err(by_puuid_error)?;
+    if match_ids.is_empty() {
+        println!("Aucun nouveau match pour {}", name);
+        return Ok(PollOutcome::UpToDate);
+    }
+
+    // Traite les matchs du plus ancien au plus récent ; en cas d'erreur temporaire,
+    // on s'arrête pour reprendre à ce match au prochain passage
+    let mut game_start = last_match.game_start;
+    for match_id in match_ids {
+        if context.match_storage.lock().await.is_processed(puuid, &match_id) {
+            continue;
+        }
+        let match_details = match api::fetch_match_details(
+            &context.riot,
+            account.region,
+            &match_id,
+            puuid,
+            &secrets.friend_puuids,
+        )
+        .await
+        {
+            Ok(match_details) => match_details,
+            // Le réessayer bloquerait tous les matchs suivants
+            Err(e) if e.is_permanent() => {
+                eprintln!("Match {} ignoré pour {} : {}", match_id, name, redact(&e.to_string()));
+                context
+                    .match_storage
+                    .lock()
+                    .await
+                    .store_match(puuid.to_string(), match_id, game_start)?;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        println!("Nouveau match pour {}: {:?}", name, match_details);
+
+        match recordings::find_recording(&secrets.folder_path, &match_details)? {
+            Some(recording) => {
+                println!(
+                    "Enregistrement trouvé pour {} : {} (confiance {:.0}%)",
+                    match_id,
+                    recording.path.display(),
+                    recording.confidence * 100.0
+                );
+                let template = secrets.template_for(account, match_details.type_queue);
+                let video = metadata::build_metadata(template, &secrets.upload.tags, &name, &match_details);
+                let job = UploadJob::new(
+                    match_id.clone(),
+                    puuid.to_string(),
+                    name.clone(),
+                    account.channel.clone(),
+                    recording.path,
+                    video,
+                    playlists::playlist_titles(&secrets.playlists.by, &match_details),
+                );
+                if context.upload_queue.lock().await.enqueue(job)? {
+                    println!("Upload du match {} ajouté à la file", match_id);
+                    context.upload_notify.notify_one();
+                }
+            }
+            None if Utc::now().timestamp_millis() - match_details.game_end_timestamp
+                < recordings::RECORDING_GRACE_PERIOD_MS =>
+            {
+                // Outplayed n'a peut-être pas encore fini d'écrire le fichier : on
+                // reprendra à ce match au prochain passage
+                println!("Enregistrement du match {} pas encore disponible", match_id);
+                return Ok(PollOutcome::RecordingPending);
+            }
+            None => {
+                println!(
+                    "Aucun enregistrement ne correspond au match {}, upload ignoré",
+                    match_id
+                );
+            }
+        }
+
+        // Met à jour le dernier match traité dans le storage
+        game_start = match_details.game_start_timestamp / 1000;
+        context
+            .match_storage
+            .lock()
+            .await
+            .store_match(puuid.to_string(), match_id, game_start)?;
+    }
+
+    Ok(PollOutcome::UpToDate)
+}
@@ -0,0 +1,71 @@
+use serde::Deserialize;
+
+// DTO de match-v5, limités aux champs utilisés. Les champs inconnus sont ignorés.
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatchDto {
+    pub metadata: MetadataDto,
+    pub info: InfoDto,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataDto {
+    pub match_id: String,
+    #[serde(default)]
+    pub participants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoDto {
+    pub game_creation: i64,
+    pub game_duration: i64,
+    pub game_start_timestamp: i64,
+    // Absent des matchs antérieurs au patch 11.20
+    pub game_end_timestamp: Option<i64>,
+    pub game_version: String,
+    pub platform_id: String,
+    pub queue_id: u32,
+    pub participants: Vec<ParticipantDto>,
+    #[serde(default)]
+    pub teams: Vec<TeamDto>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantDto {
+    pub puuid: String,
+    pub champion_name: String,
+    #[serde(default)]
+    pub team_position: String,
+    pub team_id: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub win: bool,
+    pub riot_id_game_name: Option<String>,
+    pub riot_id_tagline: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamDto {
+    pub team_id: u32,
+    pub win: bool,
+}
+
+impl MatchDto {
+    pub fn participant(&self, puuid: &str) -> Option<&ParticipantDto> {
+        self.info.participants.iter().find(|p| p.puuid == puuid)
+    }
+}
+
+impl InfoDto {
+    /// Fin de la partie en millisecondes. Pour les anciens matchs sans
+    /// `gameEndTimestamp`, elle est déduite de la durée (alors en millisecondes).
+    pub fn end_timestamp(&self) -> i64 {
+        self.game_end_timestamp
+            .unwrap_or(self.game_start_timestamp + self.game_duration)
+    }
+}
@@ -1 +1,2 @@
-pub mod types;
\ No newline at end of file
+pub mod dto;
+pub mod types;
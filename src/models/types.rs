@@ -1,17 +1,82 @@
+use std::fmt;
 use std::str::FromStr;
 
+use crate::models::dto::MatchDto;
+
 #[derive(Debug)]
 pub struct MatchDetails {
+    pub match_id: String,
+    // Horodatages de la partie, en millisecondes depuis l'epoch
+    pub game_start_timestamp: i64,
+    pub game_end_timestamp: i64,
     pub type_queue: QueueId,
     pub champions_name: String,
-    pub role: Role,
+    // Absent des modes sans rôle (ARAM, Arena...)
+    pub role: Option<Role>,
     pub kills: u32,
     pub deaths: u32,
     pub assists: u32,
+    pub win: bool,
+    // Version complète du client, ex. "14.20.628.1234"
+    pub game_version: String,
     pub friends: Option<Vec<String>>,
+    // Riot ID (sans tag) des amis présents, dans le même ordre que `friends`
+    pub friend_names: Vec<String>,
 }
 
-#[derive(Debug)]
+impl MatchDetails {
+    pub fn from_match(
+        match_dto: &MatchDto,
+        summoner_puuid: &str,
+        friends_puuids: &[String],
+    ) -> Result<Self, String> {
+        let participant = match_dto
+            .participant(summoner_puuid)
+            .ok_or("Participant with specified PUUID not found")?;
+
+        let friend_participants: Vec<_> = match_dto
+            .info
+            .participants
+            .iter()
+            .filter(|p| friends_puuids.contains(&p.puuid))
+            .collect();
+        let friends: Vec<String> = friend_participants.iter().map(|p| p.puuid.clone()).collect();
+        let friend_names = friend_participants
+            .iter()
+            .map(|p| p.riot_id_game_name.clone().unwrap_or_else(|| p.puuid.clone()))
+            .collect();
+
+        Ok(MatchDetails {
+            match_id: match_dto.metadata.match_id.clone(),
+            game_start_timestamp: match_dto.info.game_start_timestamp,
+            game_end_timestamp: match_dto.info.end_timestamp(),
+            type_queue: QueueId::from_str(&match_dto.info.queue_id.to_string())?,
+            champions_name: participant.champion_name.clone(),
+            role: match participant.team_position.as_str() {
+                "" => None,
+                position => Some(Role::from_str(position)?),
+            },
+            kills: participant.kills,
+            deaths: participant.deaths,
+            assists: participant.assists,
+            win: participant.win,
+            game_version: match_dto.info.game_version.clone(),
+            friends: if friends.is_empty() { None } else { Some(friends) },
+            friend_names,
+        })
+    }
+
+    /// Patch de la partie (deux premiers composants de la version, ex. "14.20").
+    pub fn patch(&self) -> String {
+        self.game_version
+            .split('.')
+            .take(2)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum Role {
     TOP,
     JUNGLE,
@@ -27,7 +92,8 @@ impl FromStr for Role {
         match s {
             "TOP" => Ok(Role::TOP),
             "JUNGLE" => Ok(Role::JUNGLE),
-            "MID" => Ok(Role::MID),
+            // match-v5 renvoie "MIDDLE" ; "MID" est conservé pour compatibilité
+            "MIDDLE" | "MID" => Ok(Role::MID),
             "BOTTOM" => Ok(Role::ADC),
             "UTILITY" => Ok(Role::SUPPORT),
             _ => Err("Role not found".to_string())
@@ -35,7 +101,19 @@ impl FromStr for Role {
     }
 }
 
-#[derive(Debug)]
+impl Role {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::TOP => "Top",
+            Role::JUNGLE => "Jungle",
+            Role::MID => "Mid",
+            Role::ADC => "ADC",
+            Role::SUPPORT => "Support",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum QueueId {
     SoloQ,
     Flex,
@@ -52,4 +130,291 @@ impl FromStr for QueueId {
             _ => Ok(QueueId::NotInterested)
         }
     }
-}
\ No newline at end of file
+}
+
+impl QueueId {
+    /// Clé utilisée dans la configuration (`upload.queue_templates`).
+    pub fn key(&self) -> &'static str {
+        match self {
+            QueueId::SoloQ => "soloq",
+            QueueId::Flex => "flex",
+            QueueId::NotInterested => "other",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QueueId::SoloQ => "Solo/Duo",
+            QueueId::Flex => "Flex",
+            QueueId::NotInterested => "Normal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RiotId {
+    pub game_name: String,
+    pub tag_line: String,
+}
+
+impl FromStr for RiotId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (game_name, tag_line) = s
+            .rsplit_once('#')
+            .ok_or_else(|| format!("Riot ID invalide (format attendu : Nom#TAG) : {}", s))?;
+        let game_name = game_name.trim();
+        let tag_line = tag_line.trim();
+        if game_name.is_empty() || tag_line.is_empty() {
+            return Err(format!("Riot ID invalide (format attendu : Nom#TAG) : {}", s));
+        }
+        Ok(RiotId {
+            game_name: game_name.to_string(),
+            tag_line: tag_line.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for RiotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.game_name, self.tag_line)
+    }
+}
+
+// Routage régional utilisé par match-v5 et account-v1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Americas,
+    Asia,
+    Europe,
+    Sea,
+}
+
+impl Region {
+    pub fn host(&self) -> &'static str {
+        match self {
+            Region::Americas => "americas.api.riotgames.com",
+            Region::Asia => "asia.api.riotgames.com",
+            Region::Europe => "europe.api.riotgames.com",
+            Region::Sea => "sea.api.riotgames.com",
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "americas" => Ok(Region::Americas),
+            "asia" => Ok(Region::Asia),
+            "europe" => Ok(Region::Europe),
+            "sea" => Ok(Region::Sea),
+            _ => Err(format!(
+                "Région inconnue : {} (attendu : americas, asia, europe ou sea)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Region::Americas => "americas",
+            Region::Asia => "asia",
+            Region::Europe => "europe",
+            Region::Sea => "sea",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Plateforme (serveur) d'un compte, qui préfixe aussi les match IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Br1,
+    Eun1,
+    Euw1,
+    Jp1,
+    Kr,
+    La1,
+    La2,
+    Me1,
+    Na1,
+    Oc1,
+    Ph2,
+    Ru,
+    Sg2,
+    Th2,
+    Tr1,
+    Tw2,
+    Vn2,
+}
+
+impl Platform {
+    const ALL: [Platform; 17] = [
+        Platform::Br1,
+        Platform::Eun1,
+        Platform::Euw1,
+        Platform::Jp1,
+        Platform::Kr,
+        Platform::La1,
+        Platform::La2,
+        Platform::Me1,
+        Platform::Na1,
+        Platform::Oc1,
+        Platform::Ph2,
+        Platform::Ru,
+        Platform::Sg2,
+        Platform::Th2,
+        Platform::Tr1,
+        Platform::Tw2,
+        Platform::Vn2,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Platform::Br1 => "BR1",
+            Platform::Eun1 => "EUN1",
+            Platform::Euw1 => "EUW1",
+            Platform::Jp1 => "JP1",
+            Platform::Kr => "KR",
+            Platform::La1 => "LA1",
+            Platform::La2 => "LA2",
+            Platform::Me1 => "ME1",
+            Platform::Na1 => "NA1",
+            Platform::Oc1 => "OC1",
+            Platform::Ph2 => "PH2",
+            Platform::Ru => "RU",
+            Platform::Sg2 => "SG2",
+            Platform::Th2 => "TH2",
+            Platform::Tr1 => "TR1",
+            Platform::Tw2 => "TW2",
+            Platform::Vn2 => "VN2",
+        }
+    }
+
+    /// Route régionale à utiliser pour les matchs joués sur cette plateforme.
+    pub fn region(&self) -> Region {
+        match self {
+            Platform::Br1 | Platform::La1 | Platform::La2 | Platform::Na1 => Region::Americas,
+            Platform::Jp1 | Platform::Kr => Region::Asia,
+            Platform::Eun1 | Platform::Euw1 | Platform::Me1 | Platform::Ru | Platform::Tr1 => {
+                Region::Europe
+            }
+            Platform::Oc1
+            | Platform::Ph2
+            | Platform::Sg2
+            | Platform::Th2
+            | Platform::Tw2
+            | Platform::Vn2 => Region::Sea,
+        }
+    }
+
+    /// Vérifie que le préfixe d'un match ID (ex. `EUW1_1234`) correspond à la plateforme.
+    pub fn check_match_id(&self, match_id: &str) -> Result<(), String> {
+        let prefix = match_id
+            .split_once('_')
+            .map(|(prefix, _)| prefix)
+            .ok_or_else(|| format!("Match ID invalide : {}", match_id))?;
+        if !prefix.eq_ignore_ascii_case(self.code()) {
+            return Err(format!(
+                "Le match {} n'appartient pas à la plateforme {}",
+                match_id, self
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Plateforme inconnue : {}", s))
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANKED_MATCH: &str = include_str!("fixtures/match_ranked.json");
+    const ARAM_MATCH: &str = include_str!("fixtures/match_aram.json");
+
+    fn load_match(json: &str) -> MatchDto {
+        serde_json::from_str(json).expect("fixture match-v5 invalide")
+    }
+
+    #[test]
+    fn ranked_match_details() {
+        let match_dto = load_match(RANKED_MATCH);
+        let details = MatchDetails::from_match(&match_dto, "puuid-joueur", &["puuid-ami".to_string()]).unwrap();
+
+        assert_eq!(details.match_id, "EUW1_7123456789");
+        assert!(matches!(details.type_queue, QueueId::SoloQ));
+        assert!(matches!(details.role, Some(Role::TOP)));
+        assert_eq!(details.champions_name, "Garen");
+        assert_eq!((details.kills, details.deaths, details.assists), (7, 2, 9));
+        assert!(details.win);
+        assert_eq!(details.game_start_timestamp, 1729279800000);
+        assert_eq!(details.game_end_timestamp, 1729281634000);
+        assert_eq!(details.patch(), "14.20");
+        assert_eq!(details.friends, Some(vec!["puuid-ami".to_string()]));
+        assert_eq!(details.friend_names, vec!["UnAmi".to_string()]);
+    }
+
+    #[test]
+    fn every_team_position_is_recognized() {
+        let match_dto = load_match(RANKED_MATCH);
+        for participant in &match_dto.info.participants {
+            let details = MatchDetails::from_match(&match_dto, &participant.puuid, &[]).unwrap();
+            assert!(details.role.is_some(), "rôle manquant pour {}", participant.team_position);
+        }
+    }
+
+    #[test]
+    fn aram_match_has_no_role() {
+        let match_dto = load_match(ARAM_MATCH);
+        let details = MatchDetails::from_match(&match_dto, "puuid-joueur", &[]).unwrap();
+
+        assert!(details.role.is_none());
+        assert!(matches!(details.type_queue, QueueId::NotInterested));
+        assert_eq!(details.champions_name, "Lux");
+        assert_eq!(details.friends, None);
+        assert!(details.friend_names.is_empty());
+    }
+
+    #[test]
+    fn unknown_participant_is_an_error() {
+        let match_dto = load_match(RANKED_MATCH);
+        assert!(MatchDetails::from_match(&match_dto, "puuid-inconnu", &[]).is_err());
+    }
+
+    #[test]
+    fn end_timestamp_falls_back_to_duration() {
+        let mut match_dto = load_match(RANKED_MATCH);
+        match_dto.info.game_end_timestamp = None;
+        match_dto.info.game_duration = 1_834_000;
+        assert_eq!(match_dto.info.end_timestamp(), 1729279800000 + 1_834_000);
+    }
+
+    #[test]
+    fn match_id_must_belong_to_platform() {
+        assert!(Platform::Euw1.check_match_id("EUW1_7123456789").is_ok());
+        assert!(Platform::Euw1.check_match_id("euw1_7123456789").is_ok());
+        assert!(Platform::Euw1.check_match_id("NA1_7123456789").is_err());
+        assert!(Platform::Euw1.check_match_id("7123456789").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::models::types::{Region, RiotId};
+use crate::services::riot::RiotClient;
+use crate::utils::file::write_atomic;
+use crate::utils::redact::redact;
+
+const DEFAULT_CACHE_PATH: &str = "riot_ids.json";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountDto {
+    puuid: String,
+}
+
+/// Résout les Riot IDs (`Nom#TAG`) en PUUID via account-v1, avec un cache
+/// sur disque pour éviter de refaire l'appel à chaque démarrage.
+pub struct RiotIdResolver {
+    cache_path: PathBuf,
+    cache: Mutex<HashMap<String, String>>,
+}
+
+impl RiotIdResolver {
+    pub fn new() -> Self {
+        Self::with_cache_path(PathBuf::from(DEFAULT_CACHE_PATH))
+    }
+
+    pub fn with_cache_path(cache_path: PathBuf) -> Self {
+        let cache = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            cache_path,
+            cache: Mutex::new(cache),
+        }
+    }
+
+    /// Retourne le PUUID en cache, ou interroge l'API s'il est inconnu.
+    pub async fn resolve(
+        &self,
+        client: &RiotClient,
+        region: Region,
+        riot_id: &RiotId,
+    ) -> Result<String, String> {
+        if let Some(puuid) = self.cache.lock().unwrap().get(&cache_key(riot_id)) {
+            return Ok(puuid.clone());
+        }
+        self.refresh(client, region, riot_id).await
+    }
+
+    /// Ignore le cache et interroge l'API, puis met à jour le cache.
+    pub async fn refresh(
+        &self,
+        client: &RiotClient,
+        region: Region,
+        riot_id: &RiotId,
+    ) -> Result<String, String> {
+        let puuid = fetch_puuid_by_riot_id(client, region, riot_id).await?;
+        let mut cache = self.cache.lock().unwrap();
+        cache.insert(cache_key(riot_id), puuid.clone());
+        self.save(&cache)?;
+        Ok(puuid)
+    }
+
+    fn save(&self, cache: &HashMap<String, String>) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(cache)
+            .map_err(|e| format!("Erreur lors de la sérialisation du cache des Riot IDs : {}", e))?;
+        write_atomic(&self.cache_path, content.as_bytes())
+    }
+}
+
+// Les Riot IDs ne sont pas sensibles à la casse
+fn cache_key(riot_id: &RiotId) -> String {
+    riot_id.to_string().to_lowercase()
+}
+
+async fn fetch_puuid_by_riot_id(
+    client: &RiotClient,
+    region: Region,
+    riot_id: &RiotId,
+) -> Result<String, String> {
+    let mut url = Url::parse(&format!(
+        "https://{}/riot/account/v1/accounts/by-riot-id",
+        region.host()
+    ))
+    .map_err(|e| e.to_string())?;
+    url.path_segments_mut()
+        .map_err(|_| "URL account-v1 invalide".to_string())?
+        .push(&riot_id.game_name)
+        .push(&riot_id.tag_line);
+
+    let response = client.get(region, "account-v1.by-riot-id", url).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(format!("Riot ID introuvable : {}", riot_id));
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Erreur lors de la résolution du Riot ID {} : {}",
+            riot_id,
+            response.status()
+        ));
+    }
+
+    let account: AccountDto = response.json().await.map_err(|e| redact(&e.to_string()))?;
+    Ok(account.puuid)
+}
@@ -1,175 +1,230 @@
+use chrono::Utc;
 use serde_json::Value;
-use std::{fs::DirEntry, path::PathBuf, str::FromStr};
-use oauth2::{basic::BasicClient, reqwest::async_http_client, AuthUrl, ClientId, ClientSecret, TokenUrl};
-use oauth2::{RefreshToken, TokenResponse};
-use reqwest::Client;
+use std::path::Path;
+use reqwest::{Response, StatusCode, Url};
 use serde_json::json;
+use std::io::SeekFrom;
+use std::time::Duration;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::config::UploadConfig;
+use crate::services::metadata::VideoMetadata;
+use crate::services::oauth::{google_project, TokenStore};
+use crate::services::riot::{RiotClient, RiotError};
+use crate::utils::quota::{is_quota_exceeded, QuotaTracker, VIDEO_INSERT_COST, VIDEO_LIST_COST};
+use crate::utils::redact::redact;
+use crate::utils::upload_sessions::{UploadSession, UploadSessions};
+use crate::models::dto::MatchDto;
+use crate::models::types::{MatchDetails, Platform, Region};
+
+// Taille de page maximale acceptée par match-v5
+const MATCH_IDS_PAGE_SIZE: usize = 100;
+// Garde-fou contre un rattrapage sans fin
+const MAX_MATCH_IDS_PAGES: usize = 5;
+// Taille des morceaux envoyés : YouTube exige un multiple de 256 Kio
+const CHUNK_SIZE: u64 = 32 * 256 * 1024;
+// Échecs consécutifs tolérés sur un même morceau avant d'abandonner
+const MAX_CHUNK_RETRIES: u32 = 5;
+const MEGABYTE: u64 = 1024 * 1024;
+
+pub async fn fetch_latest_match_id(
+    client: &RiotClient,
+    region: Region,
+    platform: Platform,
+    puuid: &str,
+) -> Result<String, RiotError> {
+    let url = Url::parse(&format!(
+        "https://{}/lol/match/v5/matches/by-puuid/{}/ids?start=0&count=1",
+        region.host(),
+        puuid
+    ))
+    .map_err(|e| RiotError::Invalid(e.to_string()))?;
+
+    let response: Vec<String> = client.get_json(region, "match-v5.by-puuid", url).await?;
+    let match_id = response
+        .first()
+        .cloned()
+        .ok_or_else(|| RiotError::Invalid("aucun match trouvé pour ce compte".to_string()))?;
+    platform.check_match_id(&match_id).map_err(RiotError::Invalid)?;
+    Ok(match_id)
+}
 
+/// Récupère les matchs joués depuis `start_time` (secondes depuis l'epoch),
+/// page par page, en s'arrêtant au dernier match déjà traité. Les IDs sont
+/// renvoyés du plus ancien au plus récent.
+pub async fn fetch_match_ids_since(
+    client: &RiotClient,
+    region: Region,
+    platform: Platform,
+    puuid: &str,
+    start_time: i64,
+    last_match_id: &str,
+) -> Result<Vec<String>, RiotError> {
+    let mut match_ids = Vec::new();
+
+    for page in 0..MAX_MATCH_IDS_PAGES {
+        let url = Url::parse(&format!(
+            "https://{}/lol/match/v5/matches/by-puuid/{}/ids?startTime={}&start={}&count={}",
+            region.host(),
+            puuid,
+            start_time,
+            page * MATCH_IDS_PAGE_SIZE,
+            MATCH_IDS_PAGE_SIZE
+        ))
+        .map_err(|e| RiotError::Invalid(e.to_string()))?;
+
+        let ids: Vec<String> = client.get_json(region, "match-v5.by-puuid", url).await?;
+        let page_len = ids.len();
+
+        // Les IDs arrivent du plus récent au plus ancien
+        for match_id in ids {
+            if match_id == last_match_id {
+                match_ids.reverse();
+                return Ok(match_ids);
+            }
+            // Un ID d'une autre plateforme (transfert de serveur, plateforme mal
+            // configurée) ne doit pas bloquer les autres matchs du compte
+            if let Err(e) = platform.check_match_id(&match_id) {
+                eprintln!("{}, match ignoré", e);
+                continue;
+            }
+            match_ids.push(match_id);
+        }
 
-use crate::get_token;
-use crate::models::types::{MatchDetails, QueueId, Role};
-
-pub async fn fetch_latest_match_id(api_key: &str, puuid: &str) -> Result<String, String> {
-    let url = format!(
-        "https://europe.api.riotgames.com/lol/match/v5/matches/by-puuid/{}/ids?start=0&count=1&api_key={}",
-        puuid, api_key
-    );
+        if page_len < MATCH_IDS_PAGE_SIZE {
+            break;
+        }
+    }
 
-    let response: Vec<String> = reqwest::Client::new()
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?
-        .json()
-        .await
-        .map_err(|e| e.to_string())?;
-    println!("Response: {:?}", response);
-    response
-        .get(0)
-        .cloned()
-        .ok_or_else(|| "No match ID found".to_string())
+    match_ids.reverse();
+    Ok(match_ids)
 }
 
+pub async fn fetch_match(client: &RiotClient, region: Region, match_id: &str) -> Result<MatchDto, RiotError> {
+    let url = Url::parse(&format!(
+        "https://{}/lol/match/v5/matches/{}",
+        region.host(),
+        match_id
+    ))
+    .map_err(|e| RiotError::Invalid(e.to_string()))?;
+
+    client.get_json(region, "match-v5.match", url).await
+}
 
 pub async fn fetch_match_details(
-    api_key: &str,
+    client: &RiotClient,
+    region: Region,
     match_id: &str,
     summoner_puuid: &str,
-    friends_puuids: &Vec<String>,
-) -> Result<MatchDetails, String> {
-    let url = format!(
-        "https://europe.api.riotgames.com/lol/match/v5/matches/{}?api_key={}",
-        match_id, api_key
-    );
-
-    // Récupère le JSON brut
-    let response: Value = reqwest::Client::new()
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?
-        .json()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let queue_id = response["info"]["queueId"]
-        .as_u64()
-        .ok_or("Queue ID not found")?
-        .to_string();
-
-    let queue = QueueId::from_str(&queue_id)?;
-
-    let participants = response["info"]["participants"]
-        .as_array()
-        .ok_or("Participants not found")?;
-
-    // Trouve les informations du joueur correspondant à notre PUUID
-    let participant = participants
-        .iter()
-        .find(|p| p["puuid"].as_str() == Some(summoner_puuid))
-        .ok_or("Participant with specified PUUID not found")?;
-
-    let champion_name = participant["championName"]
-        .as_str()
-        .ok_or("Champion name not found")?
-        .to_string();
-
-    let role_str = participant["teamPosition"]
-        .as_str()
-        .ok_or("Role not found")?;
-
-    let role = Role::from_str(role_str)?;
-
-    let kills = participant["kills"]
-        .as_u64()
-        .ok_or("Kills not found")? as u32;
-
-    let deaths = participant["deaths"]
-        .as_u64()
-        .ok_or("Deaths not found")? as u32;
-
-    let assists = participant["assists"]
-        .as_u64()
-        .ok_or("Assists not found")? as u32;
-
-    let friends: Option<Vec<String>> = {
-        let friends_list: Vec<String> = participants
-            .iter()
-            .filter_map(|p| {
-                let puuid = p["puuid"].as_str()?;
-                if friends_puuids.contains(&puuid.to_string()) {
-                    Some(puuid.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect();
-        if friends_list.is_empty() {
-            None
-        } else {
-            Some(friends_list)
-        }
-    };
-
-    // Crée une instance de MatchDetails
-    Ok(MatchDetails {
-        type_queue: queue,
-        champions_name: champion_name,
-        role,
-        kills,
-        deaths,
-        assists,
-        friends,
-    })
+    friends_puuids: &[String],
+) -> Result<MatchDetails, RiotError> {
+    let match_dto = fetch_match(client, region, match_id).await?;
+    MatchDetails::from_match(&match_dto, summoner_puuid, friends_puuids).map_err(RiotError::Invalid)
 }
 
-pub async fn upload_video(folder_path: &PathBuf) -> Result<(), String> {
-    let file = check_folder_and_print_file_path(folder_path).await?;
-    let video_data = fs::read(&file).await.map_err(|e| format!("Erreur de lecture du fichier : {}", e))?;
-
-    let client_secret = std::fs::read_to_string("client_secret.json").map_err(|e| format!("Erreur lors du chargement du fichier client_secret.json : {}", e))?;
-    let client_info: Value = serde_json::from_str(&client_secret).map_err(|e| format!("Erreur de parsing JSON : {}", e))?;
-
-    let client_id = ClientId::new(client_info["installed"]["client_id"].as_str().unwrap().to_string());
-    let client_secret = ClientSecret::new(client_info["installed"]["client_secret"].as_str().unwrap().to_string());
-    let token_url = TokenUrl::new(client_info["installed"]["token_uri"].as_str().unwrap().to_string()).unwrap();
+pub async fn upload_video(
+    file: &Path,
+    video: &VideoMetadata,
+    upload_config: &UploadConfig,
+    token_store: &TokenStore,
+    sessions: &Mutex<UploadSessions>,
+    quota: &Mutex<QuotaTracker>,
+) -> Result<String, YoutubeError> {
+    let total_size = fs::metadata(file)
+        .await
+        .map_err(|e| format!("Erreur de lecture du fichier : {}", e))?
+        .len();
+    if total_size == 0 {
+        return Err(format!("{} est vide", file.display()).into());
+    }
 
     // Chargez le jeton existant ou actualisez-le
-    let access_token = if let Ok(token) = get_token().await {
-        token
-    } else {
-        refresh_access_token(&client_id, &client_secret, &token_url, "VOTRE_REFRESH_TOKEN").await?
-    };
+    let mut access_token = token_store.access_token().await?;
 
     let client = reqwest::Client::new();
     let metadata = json!({
         "snippet": {
-            "title": "Test Video Title",
-            "description": "Test Video Description",
-            "tags": ["Rust", "YouTube", "API"],
-            "categoryId": "20" // Gaming
+            "title": video.title,
+            "description": video.description,
+            "tags": video.tags,
+            "categoryId": upload_config.category_id
         },
         "status": {
-            "privacyStatus": "unlisted"
+            "privacyStatus": upload_config.privacy_status
         }
     });
 
-    let init_url = "https://www.googleapis.com/upload/youtube/v3/videos?uploadType=resumable&part=snippet,status";
-    let init_response = client
-        .post(init_url)
-        .bearer_auth(&access_token)
-        .header("Content-Type", "application/json")
-        .json(&metadata)
-        .send()
+    // Reprend la session d'un upload interrompu (ex. redémarrage) si elle est encore valide
+    let previous = sessions
+        .lock()
         .await
-        .map_err(|e| format!("Erreur lors de l'initialisation de l'upload : {}", e))?;
+        .get(file)
+        .filter(|session| session.matches(total_size, token_store.channel(), &metadata))
+        .cloned();
+    if let Some(session) = previous {
+        match query_upload_status(&client, &session.session_uri, &access_token, total_size).await? {
+            UploadStatus::Complete(video_id) => {
+                println!("Upload de {} déjà terminé lors d'une exécution précédente", file.display());
+                sessions.lock().await.remove(file)?;
+                return Ok(video_id);
+            }
+            UploadStatus::Incomplete(offset) => {
+                println!(
+                    "Reprise de l'upload de {} à {} / {} Mo",
+                    file.display(),
+                    offset / MEGABYTE,
+                    total_size / MEGABYTE
+                );
+                let upload = ResumableUpload {
+                    client: &client,
+                    upload_url: &session.session_uri,
+                    file,
+                    total_size,
+                    token_store,
+                    sessions,
+                };
+                let result = upload_chunks(&upload, offset, access_token).await;
+                return Ok(finish_upload(file, result, sessions).await?);
+            }
+            UploadStatus::Expired => {
+                println!("Session d'upload expirée pour {}, nouvelle session", file.display());
+            }
+        }
+    }
+
+    // Chaque nouvelle session d'upload coûte un videos.insert
+    let project = google_project()?;
+    {
+        let mut quota = quota.lock().await;
+        if !quota.try_reserve(&project, VIDEO_INSERT_COST, upload_config.daily_quota)? {
+            return Err(YoutubeError::QuotaExceeded);
+        }
+        println!(
+            "Quota YouTube : {} / {} unités utilisées aujourd'hui",
+            quota.used_today(&project),
+            upload_config.daily_quota
+        );
+    }
+
+    let init_url = "https://www.googleapis.com/upload/youtube/v3/videos?uploadType=resumable&part=snippet,status";
+    let mut init_response = init_upload(&client, init_url, &access_token, &metadata, total_size).await?;
+
+    // Jeton refusé malgré sa date d'expiration (révoqué côté Google) : on le renouvelle une fois
+    if init_response.status() == StatusCode::UNAUTHORIZED {
+        access_token = token_store.refresh_after_unauthorized(&access_token).await?;
+        init_response = init_upload(&client, init_url, &access_token, &metadata, total_size).await?;
+    }
 
     if !init_response.status().is_success() {
-        return Err(format!(
-            "Erreur d'initialisation : {}",
-            init_response.text().await.unwrap_or_default()
-        ));
+        let body = init_response.text().await.unwrap_or_default();
+        if is_quota_exceeded(&body) {
+            quota.lock().await.mark_exhausted(&project)?;
+            return Err(YoutubeError::QuotaExceeded);
+        }
+        return Err(format!("Erreur d'initialisation : {}", body).into());
     }
 
     let upload_url = init_response
@@ -177,83 +232,382 @@ pub async fn upload_video(folder_path: &PathBuf) -> Result<(), String> {
         .get("Location")
         .ok_or("En-tête Location manquant dans la réponse")?
         .to_str()
-        .map_err(|_| "En-tête Location non valide")?;
+        .map_err(|_| "En-tête Location non valide")?
+        .to_string();
+    sessions.lock().await.insert(UploadSession {
+        file: file.to_path_buf(),
+        size: total_size,
+        channel: token_store.channel().to_string(),
+        session_uri: upload_url.clone(),
+        confirmed_offset: 0,
+        metadata,
+        created_at: Utc::now(),
+    })?;
 
     // Étape 5 : Téléversez la vidéo
-    let upload_response = client
-        .put(upload_url)
-        .bearer_auth(&access_token)
-        .body(video_data)
+    let upload = ResumableUpload {
+        client: &client,
+        upload_url: &upload_url,
+        file,
+        total_size,
+        token_store,
+        sessions,
+    };
+    let result = upload_chunks(&upload, 0, access_token).await;
+    Ok(finish_upload(file, result, sessions).await?)
+}
+
+// Oublie la session une fois l'upload terminé ou la session expirée ; après
+// toute autre erreur elle est conservée pour reprendre au prochain essai
+async fn finish_upload(
+    file: &Path,
+    result: Result<String, UploadError>,
+    sessions: &Mutex<UploadSessions>,
+) -> Result<String, String> {
+    match result {
+        Ok(video_id) => {
+            sessions.lock().await.remove(file)?;
+            println!("Vidéo téléversée avec succès ! (ID {})", video_id);
+            Ok(video_id)
+        }
+        Err(UploadError::Expired) => {
+            sessions.lock().await.remove(file)?;
+            Err("Session d'upload expirée".to_string())
+        }
+        Err(UploadError::Other(e)) => Err(e),
+    }
+}
+
+async fn init_upload(
+    client: &reqwest::Client,
+    init_url: &str,
+    access_token: &str,
+    metadata: &Value,
+    total_size: u64,
+) -> Result<Response, String> {
+    client
+        .post(init_url)
+        .bearer_auth(access_token)
+        .header("Content-Type", "application/json")
+        .header("X-Upload-Content-Type", "video/*")
+        .header("X-Upload-Content-Length", total_size)
+        .json(metadata)
         .send()
         .await
-        .map_err(|e| format!("Erreur lors du téléversement de la vidéo : {}", e))?;
+        .map_err(|e| format!("Erreur lors de l'initialisation de l'upload : {}", redact(&e.to_string())))
+}
+
+/// Erreur d'un appel à YouTube ; le quota épuisé, la vidéo en cours de
+/// traitement et le jeton aux droits insuffisants sont distingués pour
+/// reporter le travail plutôt que de le compter comme un échec.
+#[derive(Debug)]
+pub enum YoutubeError {
+    QuotaExceeded,
+    // Vidéo reçue mais pas encore traitée par YouTube
+    Processing,
+    // Jeton obtenu avec le seul scope youtube.upload : il faut relancer `auth add <canal>`
+    InsufficientScope,
+    Other(String),
+}
 
-    if !upload_response.status().is_success() {
-        return Err(format!(
-            "Erreur de téléversement : {}",
-            upload_response.text().await.unwrap_or_default()
-        ));
+/// Reconnaît un refus dû aux scopes du jeton dans le corps d'une réponse de l'API YouTube.
+pub fn is_insufficient_scope(body: &str) -> bool {
+    let Ok(error) = serde_json::from_str::<Value>(body) else {
+        return false;
+    };
+    let error = &error["error"];
+    let reasons = error["errors"].as_array().into_iter().flatten();
+    let details = error["details"].as_array().into_iter().flatten();
+    reasons
+        .chain(details)
+        .any(|e| matches!(e["reason"].as_str(), Some("insufficientPermissions" | "ACCESS_TOKEN_SCOPE_INSUFFICIENT")))
+}
+
+impl From<String> for YoutubeError {
+    fn from(e: String) -> Self {
+        YoutubeError::Other(e)
     }
+}
 
-    println!("Vidéo téléversée avec succès !");
-    Ok(())
+impl From<&str> for YoutubeError {
+    fn from(e: &str) -> Self {
+        YoutubeError::Other(e.to_string())
+    }
 }
 
+// État d'une session d'upload résumable côté YouTube
+enum UploadStatus {
+    // Nombre d'octets confirmés par le serveur
+    Incomplete(u64),
+    // ID de la vidéo créée
+    Complete(String),
+    // Session inconnue ou expirée : il faut en ouvrir une nouvelle
+    Expired,
+}
 
-// Fonction pour rafraîchir le jeton d'accès
-async fn refresh_access_token(
-    client_id: &ClientId,
-    client_secret: &ClientSecret,
-    token_url: &TokenUrl,
-    refresh_token: &str,
-) -> Result<String, String> {
-    let client = BasicClient::new(
-        client_id.clone(),
-        Some(client_secret.clone()),
-        AuthUrl::new("https://accounts.google.com/o/oauth2/auth".to_string()).unwrap(),
-        Some(token_url.clone()),
-    );
-
-    let refresh_token = RefreshToken::new(refresh_token.to_string());
-    let token = client
-        .exchange_refresh_token(&refresh_token)
-        .request_async(async_http_client)
-        .await
-        .map_err(|e| format!("Erreur lors du rafraîchissement du jeton : {}", e))?;
+// Session d'upload ouverte pour un fichier
+struct ResumableUpload<'a> {
+    client: &'a reqwest::Client,
+    upload_url: &'a str,
+    file: &'a Path,
+    total_size: u64,
+    token_store: &'a TokenStore,
+    sessions: &'a Mutex<UploadSessions>,
+}
+
+enum UploadError {
+    Expired,
+    Other(String),
+}
 
-    Ok(token.access_token().secret().to_string())
+impl From<String> for UploadError {
+    fn from(e: String) -> Self {
+        UploadError::Other(e)
+    }
 }
 
-pub async fn check_folder_and_print_file_path(folder_path: &PathBuf) -> Result<String, String> {
-    if folder_path.is_dir() {
-        let mut entries = std::fs::read_dir(folder_path)
-            .map_err(|e| e.to_string())?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| entry.path().is_dir());
-
-        if let Some(folder) = entries.next() {
-            let folder_path = folder.path();
-            let mut file_entries = std::fs::read_dir(&folder_path)
-                .map_err(|e| e.to_string())?
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| entry.path().is_file());
-
-            if let Some(file) = file_entries.next() {
-                if let Some(file_name) = file.file_name().to_str() {
-                    if file_name.ends_with(".mp4") {
-                        return Ok(file.path().to_string_lossy().to_string());
-                    }
+/// Envoie le fichier morceau par morceau depuis le disque. Après une erreur
+/// réseau ou serveur, l'état de la session est redemandé pour ne renvoyer
+/// que les octets manquants.
+async fn upload_chunks(
+    upload: &ResumableUpload<'_>,
+    mut offset: u64,
+    mut access_token: String,
+) -> Result<String, UploadError> {
+    let ResumableUpload {
+        client,
+        upload_url,
+        file,
+        total_size,
+        token_store,
+        sessions,
+    } = *upload;
+    let mut video = fs::File::open(file)
+        .await
+        .map_err(|e| format!("Erreur de lecture du fichier : {}", e))?;
+    let mut failures = 0;
+
+    while offset < total_size {
+        let chunk = read_chunk(&mut video, offset, total_size).await?;
+        let chunk_end = offset + chunk.len() as u64 - 1;
+
+        let result = client
+            .put(upload_url)
+            .bearer_auth(&access_token)
+            .header("Content-Range", format!("bytes {}-{}/{}", offset, chunk_end, total_size))
+            .body(chunk)
+            .send()
+            .await;
+
+        let retryable_error = match result {
+            Ok(response) => match response.status() {
+                StatusCode::OK | StatusCode::CREATED => return Ok(video_id(response).await?),
+                StatusCode::PERMANENT_REDIRECT => {
+                    offset = confirmed_offset(&response);
+                    failures = 0;
+                    sessions.lock().await.update_offset(file, offset)?;
+                    println!(
+                        "Upload de {} : {} / {} Mo",
+                        file.display(),
+                        offset / MEGABYTE,
+                        total_size / MEGABYTE
+                    );
+                    continue;
+                }
+                StatusCode::UNAUTHORIZED => {
+                    access_token = token_store.refresh_after_unauthorized(&access_token).await?;
+                    format!("jeton refusé ({})", response.status())
                 }
+                StatusCode::NOT_FOUND | StatusCode::GONE => return Err(UploadError::Expired),
+                status if status.is_server_error() => format!("erreur serveur ({})", status),
+                status => {
+                    return Err(UploadError::Other(format!(
+                        "Erreur de téléversement ({}) : {}",
+                        status,
+                        response.text().await.unwrap_or_default()
+                    )))
+                }
+            },
+            Err(e) => redact(&e.to_string()),
+        };
+
+        failures += 1;
+        if failures > MAX_CHUNK_RETRIES {
+            return Err(UploadError::Other(format!(
+                "Erreur lors du téléversement de la vidéo à l'octet {} : {}",
+                offset, retryable_error
+            )));
+        }
+        eprintln!(
+            "Morceau refusé à l'octet {} ({}), nouvelle tentative {}/{}",
+            offset, retryable_error, failures, MAX_CHUNK_RETRIES
+        );
+        sleep(Duration::from_secs(1 << failures)).await;
+
+        // Le serveur a pu recevoir une partie du morceau : on repart de ce qu'il a confirmé
+        match query_upload_status(client, upload_url, &access_token, total_size).await {
+            Ok(UploadStatus::Complete(video_id)) => return Ok(video_id),
+            Ok(UploadStatus::Incomplete(confirmed)) => {
+                offset = confirmed;
+                sessions.lock().await.update_offset(file, offset)?;
             }
+            Ok(UploadStatus::Expired) => return Err(UploadError::Expired),
+            Err(e) => eprintln!("Impossible de récupérer l'état de l'upload : {}", e),
         }
-    } else {
-        return Err("Provided path is not a directory".to_string());
     }
-    Err("No file found in the provided directory".to_string())
+
+    // Tous les octets ont été confirmés mais la réponse finale a été perdue
+    match query_upload_status(client, upload_url, &access_token, total_size).await? {
+        UploadStatus::Complete(video_id) => Ok(video_id),
+        UploadStatus::Incomplete(confirmed) => Err(UploadError::Other(format!(
+            "Upload incomplet : {} octets confirmés sur {}",
+            confirmed, total_size
+        ))),
+        UploadStatus::Expired => Err(UploadError::Expired),
+    }
 }
 
-pub async fn delete_video_from_folder(video_path: &str) -> Result<(), String> {
-    println!("Deleting video from {}", video_path);
-    Ok(())
+async fn read_chunk(video: &mut fs::File, offset: u64, total_size: u64) -> Result<Vec<u8>, String> {
+    let len = CHUNK_SIZE.min(total_size - offset) as usize;
+    let mut chunk = vec![0; len];
+    video
+        .seek(SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("Erreur de lecture du fichier : {}", e))?;
+    video
+        .read_exact(&mut chunk)
+        .await
+        .map_err(|e| format!("Erreur de lecture du fichier : {}", e))?;
+    Ok(chunk)
 }
 
+// Demande au serveur combien d'octets il a reçus (`Content-Range: bytes */total`)
+async fn query_upload_status(
+    client: &reqwest::Client,
+    upload_url: &str,
+    access_token: &str,
+    total_size: u64,
+) -> Result<UploadStatus, String> {
+    let response = client
+        .put(upload_url)
+        .bearer_auth(access_token)
+        .header("Content-Length", 0)
+        .header("Content-Range", format!("bytes */{}", total_size))
+        .send()
+        .await
+        .map_err(|e| redact(&e.to_string()))?;
+
+    match response.status() {
+        StatusCode::OK | StatusCode::CREATED => Ok(UploadStatus::Complete(video_id(response).await?)),
+        StatusCode::PERMANENT_REDIRECT => Ok(UploadStatus::Incomplete(confirmed_offset(&response))),
+        StatusCode::NOT_FOUND | StatusCode::GONE => Ok(UploadStatus::Expired),
+        status => Err(format!("statut inattendu {}", status)),
+    }
+}
+
+// La réponse finale d'un upload contient la ressource vidéo créée
+async fn video_id(response: Response) -> Result<String, String> {
+    let video: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Réponse de fin d'upload invalide : {}", e))?;
+    video["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "ID de la vidéo absent de la réponse de fin d'upload".to_string())
+}
+
+// Lit l'en-tête `Range: bytes=0-N` d'une réponse 308 ; absent si rien n'a été reçu
+fn confirmed_offset(response: &Response) -> u64 {
+    response
+        .headers()
+        .get("Range")
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.rsplit('-').next())
+        .and_then(|last_byte| last_byte.trim().parse::<u64>().ok())
+        .map_or(0, |last_byte| last_byte + 1)
+}
+
+/// Vérifie que YouTube a fini de traiter la vidéo avant de toucher à
+/// l'enregistrement local : `uploaded` signifie seulement que les octets sont
+/// reçus, le traitement peut encore échouer.
+pub async fn verify_upload(
+    video_id: &str,
+    token_store: &TokenStore,
+    quota: &Mutex<QuotaTracker>,
+) -> Result<(), YoutubeError> {
+    quota.lock().await.record(&google_project()?, VIDEO_LIST_COST)?;
+
+    let client = reqwest::Client::new();
+    let request = |access_token: &str| {
+        client
+            .get("https://www.googleapis.com/youtube/v3/videos")
+            .bearer_auth(access_token)
+            .query(&[("part", "status"), ("id", video_id)])
+            .send()
+    };
+    let mut access_token = token_store.access_token().await?;
+    let mut response = request(&access_token)
+        .await
+        .map_err(|e| format!("Erreur lors de la vérification de la vidéo : {}", redact(&e.to_string())))?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        access_token = token_store.refresh_after_unauthorized(&access_token).await?;
+        response = request(&access_token)
+            .await
+            .map_err(|e| format!("Erreur lors de la vérification de la vidéo : {}", redact(&e.to_string())))?;
+    }
+
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        if is_quota_exceeded(&body) {
+            quota.lock().await.mark_exhausted(&google_project()?)?;
+            return Err(YoutubeError::QuotaExceeded);
+        }
+        if is_insufficient_scope(&body) {
+            return Err(YoutubeError::InsufficientScope);
+        }
+        return Err(format!("Erreur lors de la vérification de la vidéo : {}", body).into());
+    }
+
+    let videos: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Réponse YouTube invalide : {}", e))?;
+    let status = &videos["items"][0]["status"];
+    match status["uploadStatus"].as_str() {
+        Some("processed") => Ok(()),
+        Some("uploaded") => Err(YoutubeError::Processing),
+        Some(upload_status) => Err(format!(
+            "Vidéo {} dans l'état {} ({})",
+            video_id,
+            upload_status,
+            status["failureReason"]
+                .as_str()
+                .or(status["rejectionReason"].as_str())
+                .unwrap_or("raison inconnue")
+        )
+        .into()),
+        None => Err(format!("Vidéo {} introuvable sur YouTube", video_id).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insufficient_scope_is_recognized() {
+        let body = r#"{"error":{"code":403,"message":"Request had insufficient authentication scopes.",
+            "errors":[{"domain":"global","reason":"insufficientPermissions"}],"status":"PERMISSION_DENIED"}}"#;
+        assert!(is_insufficient_scope(body));
+        let body = r#"{"error":{"code":403,"details":[{"reason":"ACCESS_TOKEN_SCOPE_INSUFFICIENT"}]}}"#;
+        assert!(is_insufficient_scope(body));
+    }
+
+    #[test]
+    fn other_errors_are_not_scope_errors() {
+        let body = r#"{"error":{"code":403,"errors":[{"reason":"quotaExceeded"}]}}"#;
+        assert!(!is_insufficient_scope(body));
+        assert!(!is_insufficient_scope("Forbidden"));
+    }
+}
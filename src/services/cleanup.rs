@@ -0,0 +1,131 @@
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use sysinfo::Disks;
+use tokio::fs;
+use tokio::sync::Mutex;
+
+use crate::config::CleanupConfig;
+use crate::services::recordings;
+use crate::utils::audit::{append_audit, AuditAction, AuditEntry};
+use crate::utils::storage::{MatchStorage, UploadedRecording};
+
+const GIGABYTE: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Libère de la place quand le volume de `folder_path` passe sous le seuil
+/// d'espace libre ou que les enregistrements dépassent leur quota, en
+/// supprimant d'abord les plus anciens enregistrements déjà uploadés.
+/// Les enregistrements non uploadés ne sont jamais touchés.
+pub async fn free_disk_space(
+    folder_path: &Path,
+    config: &CleanupConfig,
+    storage: &Mutex<MatchStorage>,
+) -> Result<(), String> {
+    if config.min_free_gb.is_none() && config.max_folder_gb.is_none() {
+        return Ok(());
+    }
+
+    let free = available_space(folder_path)?;
+    let used = recordings::folder_size(folder_path)?;
+    let missing_free = config
+        .min_free_gb
+        .map_or(0, |min_free| to_bytes(min_free).saturating_sub(free));
+    let over_quota = config
+        .max_folder_gb
+        .map_or(0, |max_folder| used.saturating_sub(to_bytes(max_folder)));
+    let to_free = missing_free.max(over_quota);
+    if to_free == 0 {
+        return Ok(());
+    }
+
+    println!(
+        "Espace disque : {:.1} Go libres, enregistrements {:.1} Go, {:.1} Go à libérer",
+        free as f64 / GIGABYTE,
+        used as f64 / GIGABYTE,
+        to_free as f64 / GIGABYTE
+    );
+
+    // Les plus anciens enregistrements d'abord, d'après leur date de modification
+    let uploaded = storage.lock().await.uploaded_recordings();
+    let mut candidates: Vec<(SystemTime, u64, UploadedRecording)> = Vec::new();
+    for recording in uploaded {
+        match fs::metadata(&recording.file).await {
+            Ok(metadata) => candidates.push((
+                metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                metadata.len(),
+                recording,
+            )),
+            // Déjà déplacé ou supprimé (rétention, utilisateur) : plus rien à suivre
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                storage.lock().await.forget_upload(&recording.file)?;
+            }
+            Err(e) => eprintln!("Impossible de lire {} : {}", recording.file.display(), e),
+        }
+    }
+    candidates.sort_by_key(|(modified, ..)| *modified);
+
+    let mut freed = 0;
+    let mut removed = 0;
+    for (_, size, recording) in candidates {
+        if freed >= to_free {
+            break;
+        }
+        let file = recording.file;
+        if let Err(e) = fs::remove_file(&file).await {
+            eprintln!("Impossible de supprimer {} : {}", file.display(), e);
+            continue;
+        }
+        println!("{} supprimé ({:.1} Go)", file.display(), size as f64 / GIGABYTE);
+        storage.lock().await.forget_upload(&file)?;
+        append_audit(&AuditEntry {
+            at: Utc::now(),
+            action: AuditAction::Reclaimed,
+            file,
+            destination: None,
+            size,
+            match_id: Some(recording.match_id),
+            video_id: Some(recording.video_id),
+        })?;
+        freed += size;
+        removed += 1;
+    }
+
+    println!(
+        "Nettoyage : {} enregistrement(s) supprimé(s), {:.1} Go libérés",
+        removed,
+        freed as f64 / GIGABYTE
+    );
+    if freed < to_free {
+        eprintln!(
+            "Il manque encore {:.1} Go : aucun autre enregistrement uploadé à supprimer",
+            (to_free - freed) as f64 / GIGABYTE
+        );
+    }
+    Ok(())
+}
+
+// Espace libre du volume contenant `folder_path` : celui dont le point de
+// montage est le plus long préfixe du chemin
+fn available_space(folder_path: &Path) -> Result<u64, String> {
+    let folder = std::fs::canonicalize(folder_path)
+        .map_err(|e| format!("Impossible de résoudre {} : {}", folder_path.display(), e))?;
+    // Sous Windows, canonicalize renvoie un chemin \\?\C:\... que les points de montage n'ont pas
+    let folder = folder
+        .to_str()
+        .and_then(|path| path.strip_prefix(r"\\?\"))
+        .map(PathBuf::from)
+        .unwrap_or(folder);
+
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| folder.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+        .ok_or_else(|| format!("Volume de {} introuvable", folder_path.display()))
+}
+
+fn to_bytes(gigabytes: f64) -> u64 {
+    (gigabytes * GIGABYTE) as u64
+}
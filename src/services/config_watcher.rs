@@ -0,0 +1,69 @@
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+
+// Délai pendant lequel les événements successifs sont regroupés (un éditeur
+// écrit souvent le fichier en plusieurs fois)
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+pub struct ConfigWatcher {
+    // Le watcher doit rester vivant pour continuer à recevoir les événements
+    _watcher: RecommendedWatcher,
+    events: mpsc::UnboundedReceiver<()>,
+}
+
+impl ConfigWatcher {
+    /// Surveille le fichier de configuration. Le dossier parent est observé
+    /// plutôt que le fichier lui-même, car beaucoup d'éditeurs remplacent le
+    /// fichier au lieu de le modifier sur place.
+    pub fn new(config_path: &Path) -> Result<Self, String> {
+        let config_path = config_path
+            .canonicalize()
+            .map_err(|e| format!("Impossible de résoudre {} : {}", config_path.display(), e))?;
+        let directory = config_path
+            .parent()
+            .map(PathBuf::from)
+            .ok_or("Le fichier de configuration n'a pas de dossier parent")?;
+        let file_name = config_path.file_name().map(|name| name.to_os_string());
+
+        let (tx, events) = mpsc::unbounded_channel();
+        let mut watcher = recommended_watcher(move |res: notify::Result<Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                return;
+            }
+            let concerns_config = event
+                .paths
+                .iter()
+                .any(|path| path.file_name().map(|name| name.to_os_string()) == file_name);
+            if concerns_config {
+                let _ = tx.send(());
+            }
+        })
+        .map_err(|e| format!("Erreur lors de la création du watcher : {}", e))?;
+
+        watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Erreur lors de la surveillance de {} : {}", directory.display(), e))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Attend la prochaine modification du fichier, une fois les événements
+    /// rapprochés regroupés.
+    pub async fn changed(&mut self) -> Option<()> {
+        self.events.recv().await?;
+        sleep(DEBOUNCE_DELAY).await;
+        while self.events.try_recv().is_ok() {}
+        Some(())
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::TemplateConfig;
+use crate::models::types::MatchDetails;
+
+// Limites imposées par YouTube sur les métadonnées d'une vidéo
+const MAX_TITLE_CHARS: usize = 100;
+const MAX_DESCRIPTION_BYTES: usize = 5000;
+// Total des tags, en comptant les virgules et les guillemets des tags à espaces
+const MAX_TAGS_CHARS: usize = 500;
+// Caractères refusés par YouTube dans le titre, la description et les tags
+const FORBIDDEN_CHARS: [char; 2] = ['<', '>'];
+
+pub const TEMPLATE_VARIABLES: [&str; 15] = [
+    "account",
+    "champion",
+    "role",
+    "kills",
+    "deaths",
+    "assists",
+    "kda",
+    "queue",
+    "result",
+    "date",
+    "time",
+    "patch",
+    "friends",
+    "with_friends",
+    "match_id",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+enum Part<'a> {
+    Text(String),
+    Variable(&'a str),
+}
+
+/// Vérifie qu'un modèle est bien formé et n'utilise que des variables connues.
+pub fn check_template(template: &str) -> Result<(), String> {
+    for part in parse(template)? {
+        if let Part::Variable(name) = part {
+            if !TEMPLATE_VARIABLES.contains(&name) {
+                return Err(format!(
+                    "variable inconnue {{{}}} (disponibles : {})",
+                    name,
+                    TEMPLATE_VARIABLES.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Construit le titre, la description et les tags d'une vidéo à partir d'un
+/// modèle, puis les rend conformes aux règles de YouTube.
+pub fn build_metadata(
+    template: &TemplateConfig,
+    extra_tags: &[String],
+    account_name: &str,
+    match_details: &MatchDetails,
+) -> VideoMetadata {
+    let variables = variables(account_name, match_details);
+
+    // Une variable vide (ex. {role} en ARAM) ne doit pas laisser de double espace
+    let title = clean_text(&render(&template.title, &variables));
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut title = truncate_chars(&title, MAX_TITLE_CHARS).to_string();
+    if title.is_empty() {
+        // YouTube refuse un titre vide
+        title = match_details.match_id.clone();
+    }
+
+    let description = clean_text(&render(&template.description, &variables));
+    let description = truncate_bytes(description.trim(), MAX_DESCRIPTION_BYTES).to_string();
+
+    let rendered_tags = template
+        .tags
+        .iter()
+        .map(|tag| render(tag, &variables))
+        .chain(extra_tags.iter().cloned());
+
+    VideoMetadata {
+        title,
+        description,
+        tags: clean_tags(rendered_tags),
+    }
+}
+
+fn variables(account_name: &str, match_details: &MatchDetails) -> HashMap<&'static str, String> {
+    let start = Local.timestamp_millis_opt(match_details.game_start_timestamp).single();
+    let kda = (match_details.kills + match_details.assists) as f64 / match_details.deaths.max(1) as f64;
+    let friends = match_details.friend_names.join(", ");
+    let with_friends = if friends.is_empty() {
+        String::new()
+    } else {
+        format!(" avec {}", friends)
+    };
+
+    HashMap::from([
+        ("account", account_name.to_string()),
+        ("champion", match_details.champions_name.clone()),
+        ("role", match_details.role.map_or("", |role| role.label()).to_string()),
+        ("kills", match_details.kills.to_string()),
+        ("deaths", match_details.deaths.to_string()),
+        ("assists", match_details.assists.to_string()),
+        ("kda", format!("{:.2}", kda)),
+        ("queue", match_details.type_queue.label().to_string()),
+        ("result", if match_details.win { "Victoire" } else { "Défaite" }.to_string()),
+        ("date", start.map(|s| s.format("%d/%m/%Y").to_string()).unwrap_or_default()),
+        ("time", start.map(|s| s.format("%H:%M").to_string()).unwrap_or_default()),
+        ("patch", match_details.patch()),
+        ("friends", friends),
+        ("with_friends", with_friends),
+        ("match_id", match_details.match_id.clone()),
+    ])
+}
+
+// Découpe un modèle en texte et variables ; `{{` et `}}` produisent des accolades
+fn parse(template: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push(c);
+            rest = &rest[2..];
+        } else if c == '{' {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("accolade non fermée dans \"{}\"", template))?;
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(Part::Variable(rest[1..end].trim()));
+            rest = &rest[end + 1..];
+        } else if c == '}' {
+            return Err(format!("accolade fermante isolée dans \"{}\" (utiliser }}}})", template));
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+fn render(template: &str, variables: &HashMap<&'static str, String>) -> String {
+    // Les modèles sont vérifiés au chargement de la configuration
+    let Ok(parts) = parse(template) else {
+        return template.to_string();
+    };
+    parts
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => text,
+            Part::Variable(name) => variables.get(name).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn clean_text(text: &str) -> String {
+    text.chars().filter(|c| !FORBIDDEN_CHARS.contains(c)).collect()
+}
+
+fn clean_tags(tags: impl Iterator<Item = String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    let mut total = 0;
+
+    for tag in tags {
+        // La virgule sert de séparateur entre tags côté YouTube
+        let tag: String = tag
+            .chars()
+            .filter(|c| !FORBIDDEN_CHARS.contains(c) && *c != ',')
+            .collect();
+        let tag = tag.trim().to_string();
+        if tag.is_empty() || cleaned.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            continue;
+        }
+        let quotes = if tag.contains(' ') { 2 } else { 0 };
+        let separator = if cleaned.is_empty() { 0 } else { 1 };
+        let cost = tag.chars().count() + quotes + separator;
+        if total + cost > MAX_TAGS_CHARS {
+            continue;
+        }
+        total += cost;
+        cleaned.push(tag);
+    }
+    cleaned
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+fn truncate_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Représentation lisible d'un modèle découpé
+    fn parts(template: &str) -> Result<Vec<String>, String> {
+        Ok(parse(template)?
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => format!("texte:{}", text),
+                Part::Variable(name) => format!("variable:{}", name),
+            })
+            .collect())
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        clean_tags(tags.iter().map(|tag| tag.to_string()))
+    }
+
+    #[test]
+    fn parse_splits_text_and_variables() {
+        assert_eq!(
+            parts("{champion} - { role }").unwrap(),
+            vec!["variable:champion", "texte: - ", "variable:role"]
+        );
+    }
+
+    #[test]
+    fn parse_unescapes_braces() {
+        assert_eq!(parts("{{{kills}}}").unwrap(), vec!["texte:{", "variable:kills", "texte:}"]);
+    }
+
+    #[test]
+    fn parse_rejects_unbalanced_braces() {
+        assert!(parts("{champion").is_err());
+        assert!(parts("champion}").is_err());
+    }
+
+    #[test]
+    fn check_template_rejects_unknown_variables() {
+        assert!(check_template("{champion} {kda}").is_ok());
+        assert!(check_template("{champion} {gold}").is_err());
+    }
+
+    #[test]
+    fn clean_tags_removes_forbidden_characters_and_duplicates() {
+        assert_eq!(
+            tags(&["<Ahri>", "Mid, lane", "ahri", "  ", "League of Legends"]),
+            vec!["Ahri", "Mid lane", "League of Legends"]
+        );
+    }
+
+    #[test]
+    fn clean_tags_respects_the_total_length() {
+        let long = "x".repeat(300);
+        let other = "y".repeat(250);
+        assert_eq!(tags(&[&long, &other, "court"]), vec![long.clone(), "court".to_string()]);
+    }
+}
@@ -1,2 +1,12 @@
+pub mod account;
 pub mod api;
-pub mod pid;
\ No newline at end of file
+pub mod cleanup;
+pub mod config_watcher;
+pub mod metadata;
+pub mod oauth;
+pub mod pid;
+pub mod playlists;
+pub mod recordings;
+pub mod retention;
+pub mod riot;
+pub mod session;
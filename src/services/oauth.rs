@@ -0,0 +1,495 @@
+use chrono::{DateTime, Duration, Utc};
+use oauth2::basic::{BasicClient, BasicErrorResponseType, BasicTokenResponse};
+use oauth2::devicecode::StandardDeviceAuthorizationResponse;
+use oauth2::reqwest::async_http_client;
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, DeviceAuthorizationUrl,
+    PkceCodeChallenge, RedirectUrl, RefreshToken, RequestTokenError, Scope, TokenResponse, TokenUrl,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout};
+
+use crate::utils::file::write_atomic;
+use crate::utils::redact::{redact, register_secret};
+
+// Le canal par défaut garde l'emplacement historique du jeton
+pub const DEFAULT_CHANNEL: &str = "default";
+const DEFAULT_TOKEN_PATH: &str = "token.json";
+const TOKENS_DIR: &str = "tokens";
+const CLIENT_SECRET_PATH: &str = "client_secret.json";
+const DEVICE_AUTHORIZATION_URL: &str = "https://oauth2.googleapis.com/device/code";
+// youtube.upload ne suffit pas pour vérifier les vidéos (videos.list) ni gérer
+// les playlists, et Google le refuse pour le flux device : les deux flux
+// demandent le scope YouTube complet
+const YOUTUBE_SCOPE: &str = "https://www.googleapis.com/auth/youtube";
+// Intervalle maximal entre deux interrogations en cas d'erreurs réseau répétées
+const DEVICE_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
+// Le jeton est renouvelé un peu avant son expiration pour ne pas expirer en cours de requête
+const EXPIRY_MARGIN_SECS: i64 = 5 * 60;
+// Temps laissé à l'utilisateur pour autoriser l'accès dans son navigateur
+const REDIRECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+// Durée de vie supposée quand Google n'indique pas expires_in
+const DEFAULT_EXPIRES_IN_SECS: i64 = 3600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    // Absent des anciens token.json : le jeton est alors considéré comme expiré
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    // Scopes accordés, renvoyés par Google à chaque obtention du jeton
+    #[serde(default)]
+    scopes: Option<Vec<String>>,
+}
+
+impl StoredToken {
+    fn from_response(response: &BasicTokenResponse, previous_refresh_token: Option<String>) -> Self {
+        let expires_in = response
+            .expires_in()
+            .and_then(|duration| Duration::from_std(duration).ok())
+            .unwrap_or_else(|| Duration::seconds(DEFAULT_EXPIRES_IN_SECS));
+        Self {
+            access_token: response.access_token().secret().to_string(),
+            // Google ne renvoie pas toujours de nouveau refresh token lors d'un rafraîchissement
+            refresh_token: response
+                .refresh_token()
+                .map(|token| token.secret().to_string())
+                .or(previous_refresh_token),
+            expires_at: Some(Utc::now() + expires_in),
+            scopes: response
+                .scopes()
+                .map(|scopes| scopes.iter().map(|scope| scope.to_string()).collect()),
+        }
+    }
+
+    fn expires_soon(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at - Duration::seconds(EXPIRY_MARGIN_SECS) <= Utc::now())
+    }
+
+    fn register_secrets(&self) {
+        register_secret(&self.access_token);
+        if let Some(refresh_token) = &self.refresh_token {
+            register_secret(refresh_token);
+        }
+    }
+}
+
+/// Méthode utilisée pour obtenir le consentement de l'utilisateur.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthFlow {
+    // Redirection vers un serveur local : nécessite un navigateur sur la machine
+    #[default]
+    Loopback,
+    // Code à saisir sur un autre appareil : pour les machines sans navigateur
+    Device,
+}
+
+enum RefreshError {
+    // Le refresh token a été révoqué ou a expiré : seule une nouvelle autorisation peut aider
+    Revoked,
+    Other(String),
+}
+
+/// Jetons OAuth2 d'une chaîne YouTube, persistés sur disque. Le jeton d'accès
+/// est renouvelé avant son expiration ou après un refus (401). Le consentement
+/// n'est jamais redemandé en arrière-plan : il passe par `auth add <canal>`.
+pub struct TokenStore {
+    channel: String,
+    path: PathBuf,
+    flow: AuthFlow,
+    token: Mutex<Option<StoredToken>>,
+}
+
+impl TokenStore {
+    pub fn new(channel: &str, flow: AuthFlow) -> Self {
+        Self::with_path(channel, token_path(channel), flow)
+    }
+
+    pub fn with_path(channel: &str, path: PathBuf, flow: AuthFlow) -> Self {
+        Self {
+            channel: channel.to_string(),
+            path,
+            flow,
+            token: Mutex::new(None),
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Demande un nouveau consentement et remplace le jeton existant.
+    pub async fn authorize_new(&self) -> Result<(), String> {
+        let mut token = self.token.lock().await;
+        let authorized = self.authorize().await?;
+        authorized.register_secrets();
+        self.save(&authorized)?;
+        *token = Some(authorized);
+        Ok(())
+    }
+
+    /// Retourne un jeton d'accès valide, en le renouvelant si nécessaire.
+    pub async fn access_token(&self) -> Result<String, String> {
+        // Le verrou est gardé pendant le renouvellement pour que les tâches
+        // concurrentes n'effectuent pas chacune leur propre rafraîchissement
+        let mut token = self.token.lock().await;
+        if token.is_none() {
+            *token = self.load()?;
+        }
+        if let Some(current) = token.as_ref().filter(|current| !current.expires_soon()) {
+            return Ok(current.access_token.clone());
+        }
+
+        let renewed = self.renew(token.as_ref()).await?;
+        let access_token = renewed.access_token.clone();
+        *token = Some(renewed);
+        Ok(access_token)
+    }
+
+    /// Vrai si le jeton connu a été accordé sans le scope YouTube complet
+    /// (anciens jetons limités à youtube.upload).
+    pub async fn lacks_youtube_scope(&self) -> bool {
+        self.token
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|token| token.scopes.as_ref())
+            .is_some_and(|scopes| !scopes.iter().any(|scope| scope == YOUTUBE_SCOPE))
+    }
+
+    /// À appeler quand l'API a refusé `rejected_token` (401) : force un
+    /// renouvellement, sauf si une autre tâche l'a déjà fait entre-temps.
+    pub async fn refresh_after_unauthorized(&self, rejected_token: &str) -> Result<String, String> {
+        let mut token = self.token.lock().await;
+        if let Some(current) = token.as_ref() {
+            if current.access_token != rejected_token {
+                return Ok(current.access_token.clone());
+            }
+        }
+
+        let renewed = self.renew(token.as_ref()).await?;
+        let access_token = renewed.access_token.clone();
+        *token = Some(renewed);
+        Ok(access_token)
+    }
+
+    async fn renew(&self, current: Option<&StoredToken>) -> Result<StoredToken, String> {
+        let refresh_token = current.and_then(|token| token.refresh_token.clone());
+        let renewed = match refresh_token {
+            Some(refresh_token) => match refresh(&refresh_token).await {
+                Ok(token) => token,
+                Err(RefreshError::Revoked) => {
+                    return Err(format!(
+                        "Jeton d'actualisation du canal {} révoqué ou expiré : relancez `auth add {}`",
+                        self.channel, self.channel
+                    ))
+                }
+                Err(RefreshError::Other(e)) => return Err(e),
+            },
+            None => {
+                return Err(format!(
+                    "Aucun jeton pour le canal {} : lancez `auth add {}`",
+                    self.channel, self.channel
+                ))
+            }
+        };
+
+        renewed.register_secrets();
+        self.save(&renewed)?;
+        Ok(renewed)
+    }
+
+    async fn authorize(&self) -> Result<StoredToken, String> {
+        match self.flow {
+            AuthFlow::Loopback => authorize_interactively().await,
+            AuthFlow::Device => authorize_with_device_code().await,
+        }
+    }
+
+    fn load(&self) -> Result<Option<StoredToken>, String> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Impossible de lire {} : {}", self.path.display(), e)),
+        };
+        let token: StoredToken = serde_json::from_str(&content)
+            .map_err(|e| format!("Fichier {} invalide : {}", self.path.display(), e))?;
+        token.register_secrets();
+        Ok(Some(token))
+    }
+
+    fn save(&self, token: &StoredToken) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(token)
+            .map_err(|e| format!("Erreur lors de la sérialisation des jetons : {}", e))?;
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Impossible de créer {} : {}", parent.display(), e))?;
+        }
+        write_atomic(&self.path, &content)?;
+        println!("Jetons sauvegardés dans {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Jetons de chaque canal, créés à la première utilisation. Chaque canal a
+/// son propre verrou : un jeton invalide ne bloque que les comptes qui
+/// uploadent vers ce canal.
+pub struct TokenStores {
+    flow: AuthFlow,
+    stores: std::sync::Mutex<HashMap<String, Arc<TokenStore>>>,
+}
+
+impl TokenStores {
+    pub fn new(flow: AuthFlow) -> Self {
+        Self {
+            flow,
+            stores: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, channel: &str) -> Arc<TokenStore> {
+        let mut stores = self.stores.lock().unwrap();
+        Arc::clone(
+            stores
+                .entry(channel.to_string())
+                .or_insert_with(|| Arc::new(TokenStore::new(channel, self.flow))),
+        )
+    }
+}
+
+/// Vérifie qu'un nom de canal peut servir de nom de fichier.
+pub fn validate_channel_name(channel: &str) -> Result<(), String> {
+    let valid = !channel.is_empty()
+        && channel
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "nom de canal invalide : \"{}\" (lettres, chiffres, - et _ uniquement)",
+            channel
+        ))
+    }
+}
+
+fn token_path(channel: &str) -> PathBuf {
+    if channel == DEFAULT_CHANNEL {
+        PathBuf::from(DEFAULT_TOKEN_PATH)
+    } else {
+        PathBuf::from(TOKENS_DIR).join(format!("{}.json", channel))
+    }
+}
+
+/// Construit le client OAuth2 à partir de client_secret.json.
+pub fn oauth_client() -> Result<BasicClient, String> {
+    let client_info = read_client_secret()?;
+    let installed = &client_info["installed"];
+
+    let field = |name: &str| -> Result<String, String> {
+        installed[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} manquant dans {}", name, CLIENT_SECRET_PATH))
+    };
+
+    let client_id = ClientId::new(field("client_id")?);
+    let client_secret = ClientSecret::new(field("client_secret")?);
+    register_secret(client_secret.secret());
+    let auth_uri = AuthUrl::new(field("auth_uri")?).map_err(|e| format!("auth_uri invalide : {}", e))?;
+    let token_uri = TokenUrl::new(field("token_uri")?).map_err(|e| format!("token_uri invalide : {}", e))?;
+
+    Ok(BasicClient::new(client_id, Some(client_secret), auth_uri, Some(token_uri)))
+}
+
+fn read_client_secret() -> Result<Value, String> {
+    let client_secret = fs::read_to_string(CLIENT_SECRET_PATH)
+        .map_err(|e| format!("Erreur lors de la lecture de {} : {}", CLIENT_SECRET_PATH, e))?;
+    serde_json::from_str(&client_secret).map_err(|e| format!("Erreur de parsing JSON : {}", e))
+}
+
+/// Projet Google auquel le quota YouTube est rattaché : tous les canaux
+/// partagent celui de client_secret.json.
+pub fn google_project() -> Result<String, String> {
+    let client_info = read_client_secret()?;
+    let installed = &client_info["installed"];
+    installed["project_id"]
+        .as_str()
+        .or_else(|| installed["client_id"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("project_id manquant dans {}", CLIENT_SECRET_PATH))
+}
+
+async fn refresh(refresh_token: &str) -> Result<StoredToken, RefreshError> {
+    let client = oauth_client().map_err(RefreshError::Other)?;
+    let response = client
+        .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| match e {
+            RequestTokenError::ServerResponse(response)
+                if *response.error() == BasicErrorResponseType::InvalidGrant =>
+            {
+                RefreshError::Revoked
+            }
+            e => RefreshError::Other(format!(
+                "Erreur lors du rafraîchissement du jeton : {}",
+                redact(&e.to_string())
+            )),
+        })?;
+
+    println!("Jeton d'accès rafraîchi.");
+    Ok(StoredToken::from_response(&response, Some(refresh_token.to_string())))
+}
+
+async fn authorize_interactively() -> Result<StoredToken, String> {
+    // Serveur temporaire sur un port libre de la boucle locale : Google y
+    // redirige le navigateur avec le code d'autorisation
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Impossible d'ouvrir le serveur de redirection local : {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Adresse du serveur de redirection inconnue : {}", e))?
+        .port();
+    let redirect_uri = RedirectUrl::new(format!("http://127.0.0.1:{}", port))
+        .map_err(|e| format!("URL de redirection invalide : {}", e))?;
+    let client = oauth_client()?.set_redirect_uri(redirect_uri);
+
+    // Générer l'URL d'autorisation
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let (auth_url, csrf_token) = client
+        .authorize_url(CsrfToken::new_random)
+        .set_pkce_challenge(pkce_challenge)
+        .add_scope(Scope::new(YOUTUBE_SCOPE.to_string()))
+        .url();
+
+    println!("Ouvrez ce lien dans votre navigateur et autorisez l'accès :");
+    println!("{}", auth_url);
+
+    let auth_code = timeout(REDIRECT_TIMEOUT, wait_for_redirect(&listener, &csrf_token))
+        .await
+        .map_err(|_| "Délai dépassé en attendant l'autorisation dans le navigateur".to_string())??;
+
+    // Échanger le code d'autorisation contre les jetons
+    let token_result = client
+        .exchange_code(auth_code)
+        .set_pkce_verifier(pkce_verifier)
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| format!("Erreur lors de l'échange du code : {}", redact(&e.to_string())))?;
+
+    println!("Jeton d'accès obtenu.");
+    if token_result.refresh_token().is_some() {
+        println!("Jeton d'actualisation obtenu.");
+    } else {
+        println!("Aucun jeton d'actualisation reçu !");
+    }
+
+    Ok(StoredToken::from_response(&token_result, None))
+}
+
+async fn authorize_with_device_code() -> Result<StoredToken, String> {
+    let device_authorization_url = DeviceAuthorizationUrl::new(DEVICE_AUTHORIZATION_URL.to_string())
+        .map_err(|e| format!("URL d'autorisation device invalide : {}", e))?;
+    let client = oauth_client()?.set_device_authorization_url(device_authorization_url);
+
+    let details: StandardDeviceAuthorizationResponse = client
+        .exchange_device_code()
+        .map_err(|e| format!("Erreur de configuration du flux device : {}", e))?
+        .add_scope(Scope::new(YOUTUBE_SCOPE.to_string()))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| format!("Erreur lors de la demande de code device : {}", redact(&e.to_string())))?;
+
+    println!(
+        "Sur un appareil disposant d'un navigateur, ouvrez {} et saisissez le code : {}",
+        details.verification_uri().as_str(),
+        details.user_code().secret()
+    );
+    println!("En attente de l'autorisation...");
+
+    // L'intervalle imposé par Google et les réponses slow_down sont respectés
+    // par oauth2 ; l'attente s'arrête à l'expiration du code
+    let token_result = client
+        .exchange_device_access_token(&details)
+        .set_max_backoff_interval(DEVICE_MAX_BACKOFF)
+        .request_async(async_http_client, sleep, None)
+        .await
+        .map_err(|e| format!("Erreur lors de l'obtention du jeton device : {}", redact(&e.to_string())))?;
+
+    println!("Jeton d'accès obtenu.");
+    if token_result.refresh_token().is_none() {
+        println!("Aucun jeton d'actualisation reçu !");
+    }
+
+    Ok(StoredToken::from_response(&token_result, None))
+}
+
+// Attend la redirection de Google et vérifie que le paramètre `state`
+// correspond à celui envoyé, pour rejeter une réponse forgée
+async fn wait_for_redirect(listener: &TcpListener, csrf_token: &CsrfToken) -> Result<AuthorizationCode, String> {
+    loop {
+        let (mut stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Erreur du serveur de redirection : {}", e))?;
+
+        let mut request_line = String::new();
+        BufReader::new(&mut stream)
+            .read_line(&mut request_line)
+            .await
+            .map_err(|e| format!("Erreur de lecture de la redirection : {}", e))?;
+
+        // Ligne de requête : "GET /?state=...&code=... HTTP/1.1"
+        let Some(target) = request_line.split_whitespace().nth(1) else {
+            continue;
+        };
+        let Ok(url) = Url::parse(&format!("http://127.0.0.1{}", target)) else {
+            continue;
+        };
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+        // Le navigateur peut faire d'autres requêtes (favicon...) : on les ignore
+        if !params.contains_key("code") && !params.contains_key("error") {
+            respond(&mut stream, "404 Not Found", "").await;
+            continue;
+        }
+
+        if params.get("state").map(String::as_str) != Some(csrf_token.secret().as_str()) {
+            respond(&mut stream, "400 Bad Request", "Paramètre state invalide.").await;
+            return Err("Paramètre state invalide dans la redirection OAuth2".to_string());
+        }
+        if let Some(error) = params.get("error") {
+            respond(&mut stream, "200 OK", "Autorisation refusée. Vous pouvez fermer cet onglet.").await;
+            return Err(format!("Autorisation refusée : {}", error));
+        }
+
+        respond(&mut stream, "200 OK", "Autorisation terminée. Vous pouvez fermer cet onglet.").await;
+        let code = params.get("code").cloned().unwrap_or_default();
+        return Ok(AuthorizationCode::new(code));
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
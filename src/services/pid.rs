@@ -1,9 +1,146 @@
-use sysinfo::System;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use sysinfo::{Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 
-pub fn is_process_running(process_name: &str) -> bool {
-    let system = System::new_all();
-    for _ in system.processes_by_name(std::ffi::OsStr::new(process_name)) {
-        return true;
+// Préfixes d'un motif de processus ; sans préfixe, le motif est un nom d'exécutable
+const PATH_PREFIX: &str = "path:";
+const CMD_PREFIX: &str = "cmd:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessEvent {
+    // Motif surveillé pour lequel au moins un processus vient d'apparaître
+    Started(String),
+    // Plus aucun processus ne correspond au motif
+    Stopped(String),
+}
+
+/// Critère de reconnaissance d'un processus, écrit dans la configuration
+/// sous la forme `nom.exe`, `path:/chemin/vers/exe` ou `cmd:extrait`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProcessMatcher {
+    Name(String),
+    Path(String),
+    CommandLine(String),
+}
+
+impl ProcessMatcher {
+    fn parse(pattern: &str) -> Self {
+        if let Some(path) = pattern.strip_prefix(PATH_PREFIX) {
+            ProcessMatcher::Path(normalize_path(path.trim()))
+        } else if let Some(cmd) = pattern.strip_prefix(CMD_PREFIX) {
+            ProcessMatcher::CommandLine(cmd.trim().to_string())
+        } else {
+            ProcessMatcher::Name(pattern.trim().to_string())
+        }
+    }
+
+    fn matches(&self, process: &Process) -> bool {
+        match self {
+            // Sous Linux, le nom du processus est tronqué à 15 caractères :
+            // on compare aussi le nom du fichier exécutable
+            ProcessMatcher::Name(name) => {
+                process.name().to_string_lossy().eq_ignore_ascii_case(name)
+                    || process
+                        .exe()
+                        .and_then(Path::file_name)
+                        .is_some_and(|exe| exe.to_string_lossy().eq_ignore_ascii_case(name))
+            }
+            ProcessMatcher::Path(path) => process
+                .exe()
+                .is_some_and(|exe| normalize_path(&exe.to_string_lossy()) == *path),
+            ProcessMatcher::CommandLine(fragment) => {
+                let cmd: Vec<_> = process.cmd().iter().map(|arg| arg.to_string_lossy()).collect();
+                cmd.join(" ").contains(fragment.as_str())
+            }
+        }
+    }
+}
+
+// Les chemins Windows ne tiennent compte ni de la casse ni du type de séparateur
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if cfg!(windows) {
+        path.to_lowercase()
+    } else {
+        path
+    }
+}
+
+/// Surveille en continu les processus correspondant aux motifs configurés,
+/// en ne rafraîchissant que la liste des processus.
+pub struct ProcessMonitor {
+    system: System,
+    running: HashSet<String>,
+}
+
+impl ProcessMonitor {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            running: HashSet::new(),
+        }
+    }
+
+    /// Lance la surveillance dans une tâche qui émet un événement à chaque
+    /// changement d'état d'un motif. Les motifs peuvent être modifiés en cours
+    /// de route via `patterns_rx`.
+    pub fn spawn(
+        mut self,
+        mut patterns_rx: watch::Receiver<Vec<String>>,
+        mut shutdown_rx: watch::Receiver<bool>,
+        interval: Duration,
+    ) -> (mpsc::Receiver<ProcessEvent>, JoinHandle<()>) {
+        let (events_tx, events_rx) = mpsc::channel(32);
+        let handle = tokio::spawn(async move {
+            while !*shutdown_rx.borrow() {
+                let patterns = patterns_rx.borrow_and_update().clone();
+                for event in self.poll(&patterns) {
+                    if events_tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = patterns_rx.changed() => {}
+                    _ = shutdown_rx.changed() => {}
+                }
+            }
+        });
+        (events_rx, handle)
+    }
+
+    /// Rafraîchit les processus et retourne les changements depuis le dernier appel.
+    pub fn poll(&mut self, patterns: &[String]) -> Vec<ProcessEvent> {
+        // Le chemin et la ligne de commande d'un processus ne changent pas :
+        // ils ne sont lus qu'à sa première apparition
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+
+        let mut events = Vec::new();
+        let mut now_running = HashSet::new();
+        for pattern in patterns {
+            let matcher = ProcessMatcher::parse(pattern);
+            if self.system.processes().values().any(|process| matcher.matches(process)) {
+                now_running.insert(pattern.clone());
+            }
+        }
+
+        for pattern in now_running.difference(&self.running) {
+            events.push(ProcessEvent::Started(pattern.clone()));
+        }
+        // Inclut les motifs retirés de la configuration alors qu'ils tournaient
+        for pattern in self.running.difference(&now_running) {
+            events.push(ProcessEvent::Stopped(pattern.clone()));
+        }
+        self.running = now_running;
+        events
     }
-    false
 }
@@ -0,0 +1,278 @@
+use chrono::{Datelike, Local, TimeZone};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::config::PlaylistConfig;
+use crate::models::types::MatchDetails;
+use crate::services::api::{is_insufficient_scope, YoutubeError};
+use crate::services::oauth::{google_project, TokenStore};
+use crate::utils::playlist_cache::PlaylistCache;
+use crate::utils::quota::{is_quota_exceeded, QuotaTracker, PLAYLIST_INSERT_COST, PLAYLIST_LIST_COST};
+use crate::utils::redact::redact;
+
+const PLAYLISTS_URL: &str = "https://www.googleapis.com/youtube/v3/playlists";
+const PLAYLIST_ITEMS_URL: &str = "https://www.googleapis.com/youtube/v3/playlistItems";
+// Taille de page maximale de l'API YouTube
+const PAGE_SIZE: &str = "50";
+const MAX_TITLE_CHARS: usize = 150;
+
+/// Critère de regroupement des vidéos en playlists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistKind {
+    Champion,
+    Role,
+    Queue,
+    Season,
+    // Une playlist par ami présent dans la partie
+    Friend,
+}
+
+/// Titres des playlists dans lesquelles ranger une partie.
+pub fn playlist_titles(kinds: &[PlaylistKind], match_details: &MatchDetails) -> Vec<String> {
+    let mut titles = Vec::new();
+    for kind in kinds {
+        match kind {
+            PlaylistKind::Champion => titles.push(match_details.champions_name.clone()),
+            PlaylistKind::Role => {
+                if let Some(role) = match_details.role {
+                    titles.push(format!("Rôle {}", role.label()));
+                }
+            }
+            PlaylistKind::Queue => titles.push(match_details.type_queue.label().to_string()),
+            PlaylistKind::Season => {
+                // Depuis 2024, une saison de League of Legends correspond à une année civile
+                if let Some(start) = Local.timestamp_millis_opt(match_details.game_start_timestamp).single() {
+                    titles.push(format!("Saison {}", start.year()));
+                }
+            }
+            PlaylistKind::Friend => titles.extend(
+                match_details
+                    .friend_names
+                    .iter()
+                    .map(|friend| format!("Avec {}", friend)),
+            ),
+        }
+    }
+
+    let mut cleaned: Vec<String> = Vec::new();
+    for title in titles {
+        let title: String = title
+            .chars()
+            .filter(|c| *c != '<' && *c != '>')
+            .take(MAX_TITLE_CHARS)
+            .collect();
+        let title = title.trim().to_string();
+        if !title.is_empty() && !cleaned.contains(&title) {
+            cleaned.push(title);
+        }
+    }
+    cleaned
+}
+
+/// Ajoute une vidéo aux playlists indiquées, en créant celles qui n'existent
+/// pas encore. Les titres ajoutés avec succès sont rangés dans `added`, même
+/// en cas d'erreur. Une playlist en échec n'empêche pas les autres, sauf si le
+/// quota YouTube est épuisé ou le jeton sans accès aux playlists. Le coût des
+/// ajouts doit avoir été réservé.
+pub async fn add_to_playlists(
+    video_id: &str,
+    titles: &[String],
+    config: &PlaylistConfig,
+    token_store: &TokenStore,
+    cache: &Mutex<PlaylistCache>,
+    quota: &Mutex<QuotaTracker>,
+    added: &mut Vec<String>,
+) -> Result<(), YoutubeError> {
+    let api = PlaylistApi {
+        client: reqwest::Client::new(),
+        config,
+        token_store,
+        cache,
+        quota,
+        project: google_project()?,
+    };
+    let mut errors = Vec::new();
+
+    for title in titles {
+        match api.add_to_playlist(video_id, title).await {
+            Ok(()) => {
+                println!("Vidéo {} ajoutée à la playlist \"{}\"", video_id, title);
+                added.push(title.clone());
+            }
+            // Les playlists restantes seront ajoutées au prochain essai
+            Err(e @ (YoutubeError::QuotaExceeded | YoutubeError::InsufficientScope)) => return Err(e),
+            Err(YoutubeError::Other(e)) => errors.push(format!("\"{}\" : {}", title, e)),
+            Err(YoutubeError::Processing) => {
+                errors.push(format!("\"{}\" : vidéo en cours de traitement", title))
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Erreur d'ajout aux playlists {}", errors.join(" ; ")).into())
+    }
+}
+
+struct PlaylistApi<'a> {
+    client: reqwest::Client,
+    config: &'a PlaylistConfig,
+    token_store: &'a TokenStore,
+    cache: &'a Mutex<PlaylistCache>,
+    quota: &'a Mutex<QuotaTracker>,
+    project: String,
+}
+
+impl PlaylistApi<'_> {
+    async fn add_to_playlist(&self, video_id: &str, title: &str) -> Result<(), YoutubeError> {
+        let playlist_id = self.resolve_playlist(title).await?;
+        let mut response = self.insert_item(&playlist_id, video_id).await?;
+
+        // La playlist en cache a été supprimée depuis : on la recrée une fois
+        if response.status() == StatusCode::NOT_FOUND {
+            self.cache.lock().await.remove(self.token_store.channel(), title)?;
+            let playlist_id = self.resolve_playlist(title).await?;
+            response = self.insert_item(&playlist_id, video_id).await?;
+        }
+        self.check_status(response).await.map(|_| ())
+    }
+
+    // Cherche la playlist dans le cache, puis sur la chaîne, et la crée en dernier
+    // recours. Le cache reste verrouillé pour que deux tâches ne la créent pas en double.
+    async fn resolve_playlist(&self, title: &str) -> Result<String, YoutubeError> {
+        let channel = self.token_store.channel();
+        let mut cache = self.cache.lock().await;
+        if let Some(playlist_id) = cache.get(channel, title) {
+            return Ok(playlist_id.clone());
+        }
+
+        let playlist_id = match self.find_playlist(title).await? {
+            Some(playlist_id) => playlist_id,
+            None => {
+                println!("Création de la playlist \"{}\"", title);
+                self.create_playlist(title).await?
+            }
+        };
+        cache.insert(channel, title.to_string(), playlist_id.clone())?;
+        Ok(playlist_id)
+    }
+
+    async fn find_playlist(&self, title: &str) -> Result<Option<String>, YoutubeError> {
+        let mut page_token: Option<String> = None;
+        loop {
+            let response = self
+                .send_authorized(PLAYLIST_LIST_COST, |access_token| {
+                    let mut request = self
+                        .client
+                        .get(PLAYLISTS_URL)
+                        .bearer_auth(access_token)
+                        .query(&[("part", "snippet"), ("mine", "true"), ("maxResults", PAGE_SIZE)]);
+                    if let Some(page_token) = &page_token {
+                        request = request.query(&[("pageToken", page_token)]);
+                    }
+                    request
+                })
+                .await?;
+            let page = self.check_status(response).await?;
+
+            let found = page["items"].as_array().into_iter().flatten().find(|item| {
+                item["snippet"]["title"].as_str() == Some(title)
+            });
+            if let Some(item) = found {
+                return Ok(item["id"].as_str().map(str::to_string));
+            }
+
+            match page["nextPageToken"].as_str() {
+                Some(next) => page_token = Some(next.to_string()),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    async fn create_playlist(&self, title: &str) -> Result<String, YoutubeError> {
+        let body = json!({
+            "snippet": { "title": title },
+            "status": { "privacyStatus": self.config.privacy_status }
+        });
+        let response = self
+            .send_authorized(PLAYLIST_INSERT_COST, |access_token| {
+                self.client
+                    .post(PLAYLISTS_URL)
+                    .bearer_auth(access_token)
+                    .query(&[("part", "snippet,status")])
+                    .json(&body)
+            })
+            .await?;
+
+        Ok(self.check_status(response).await?["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or("ID de la playlist absent de la réponse")?)
+    }
+
+    async fn insert_item(&self, playlist_id: &str, video_id: &str) -> Result<Response, YoutubeError> {
+        let body = json!({
+            "snippet": {
+                "playlistId": playlist_id,
+                "resourceId": { "kind": "youtube#video", "videoId": video_id }
+            }
+        });
+        // Coût déjà réservé avant l'ajout aux playlists
+        self.send_authorized(0, |access_token| {
+            self.client
+                .post(PLAYLIST_ITEMS_URL)
+                .bearer_auth(access_token)
+                .query(&[("part", "snippet")])
+                .json(&body)
+        })
+        .await
+    }
+
+    // Comptabilise le coût de l'appel, puis envoie la requête authentifiée et la
+    // rejoue une fois avec un jeton renouvelé si Google la refuse (401)
+    async fn send_authorized(
+        &self,
+        cost: u32,
+        build: impl Fn(&str) -> RequestBuilder,
+    ) -> Result<Response, YoutubeError> {
+        self.quota.lock().await.record(&self.project, cost)?;
+        let access_token = self.token_store.access_token().await?;
+        let response = send(build(&access_token)).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let access_token = self.token_store.refresh_after_unauthorized(&access_token).await?;
+        Ok(send(build(&access_token)).await?)
+    }
+
+    async fn check_status(&self, response: Response) -> Result<Value, YoutubeError> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            if is_quota_exceeded(&body) {
+                self.quota.lock().await.mark_exhausted(&self.project)?;
+                return Err(YoutubeError::QuotaExceeded);
+            }
+            if is_insufficient_scope(&body) {
+                return Err(YoutubeError::InsufficientScope);
+            }
+            return Err(format!("Erreur YouTube ({}) : {}", status, body).into());
+        }
+        Ok(response
+            .json()
+            .await
+            .map_err(|e| format!("Réponse YouTube invalide : {}", e))?)
+    }
+}
+
+async fn send(request: RequestBuilder) -> Result<Response, String> {
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Erreur de requête YouTube : {}", redact(&e.to_string())))
+}
@@ -0,0 +1,236 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::models::types::MatchDetails;
+
+// Profondeur maximale de recherche sous folder_path (Outplayed range les
+// enregistrements dans un sous-dossier par jeu)
+const MAX_DEPTH: usize = 3;
+// Écart toléré entre le début de l'enregistrement et le début de la partie
+// (chargement de la partie, démarrage tardif d'Outplayed)
+const START_TOLERANCE_MS: i64 = 5 * 60 * 1000;
+// Écart toléré entre la dernière écriture du fichier et la fin de la partie
+// (écran de fin, finalisation de l'encodage)
+const END_TOLERANCE_MS: i64 = 10 * 60 * 1000;
+// Délai après la fin d'une partie pendant lequel on attend qu'Outplayed ait
+// fini d'écrire l'enregistrement avant de l'abandonner
+pub const RECORDING_GRACE_PERIOD_MS: i64 = 15 * 60 * 1000;
+// En dessous de ce score, aucun enregistrement n'est considéré comme celui du match
+pub const MIN_CONFIDENCE: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub path: PathBuf,
+    // Horodatages en millisecondes depuis l'epoch
+    pub start: Option<i64>,
+    pub end: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordingMatch {
+    pub path: PathBuf,
+    pub confidence: f64,
+}
+
+/// Cherche l'enregistrement correspondant le mieux à un match, d'après ses
+/// horodatages de début et de fin. Retourne `None` si aucun n'est plausible.
+pub fn find_recording(folder_path: &Path, match_details: &MatchDetails) -> Result<Option<RecordingMatch>, String> {
+    if !folder_path.is_dir() {
+        return Err(format!("{} n'est pas un dossier", folder_path.display()));
+    }
+
+    let mut recordings = Vec::new();
+    collect_recordings(folder_path, 0, &mut recordings)?;
+
+    let best = recordings
+        .into_iter()
+        .map(|recording| {
+            let confidence = score(
+                &recording,
+                match_details.game_start_timestamp,
+                match_details.game_end_timestamp,
+            );
+            RecordingMatch {
+                path: recording.path,
+                confidence,
+            }
+        })
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+
+    Ok(best.filter(|candidate| candidate.confidence >= MIN_CONFIDENCE))
+}
+
+/// Taille totale des enregistrements présents sous `folder_path`, en octets.
+pub fn folder_size(folder_path: &Path) -> Result<u64, String> {
+    let mut recordings = Vec::new();
+    collect_recordings(folder_path, 0, &mut recordings)?;
+    Ok(recordings
+        .iter()
+        .filter_map(|recording| fs::metadata(&recording.path).ok())
+        .map(|metadata| metadata.len())
+        .sum())
+}
+
+/// Score entre 0 et 1 : 1 quand le fichier commence et se termine exactement
+/// avec la partie, 0 dès qu'un écart dépasse sa tolérance.
+pub fn score(recording: &Recording, game_start: i64, game_end: i64) -> f64 {
+    let end_score = closeness(recording.end - game_end, END_TOLERANCE_MS);
+    if end_score == 0.0 {
+        return 0.0;
+    }
+    match recording.start {
+        Some(start) => {
+            let start_score = closeness(start - game_start, START_TOLERANCE_MS);
+            if start_score == 0.0 {
+                return 0.0;
+            }
+            (start_score + end_score) / 2.0
+        }
+        // Sans date de début fiable, la fin seule ne suffit pas à être certain
+        None => end_score * 0.75,
+    }
+}
+
+fn closeness(delta_ms: i64, tolerance_ms: i64) -> f64 {
+    1.0 - (delta_ms.abs() as f64 / tolerance_ms as f64).min(1.0)
+}
+
+fn collect_recordings(directory: &Path, depth: usize, recordings: &mut Vec<Recording>) -> Result<(), String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Impossible de lire {} : {}", directory.display(), e))?;
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                collect_recordings(&path, depth + 1, recordings)?;
+            }
+            continue;
+        }
+        let is_mp4 = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("mp4"));
+        if !is_mp4 {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let Some(end) = metadata.modified().ok().and_then(to_millis) else {
+            continue;
+        };
+        // Le nom de fichier d'Outplayed est plus fiable que la date de création,
+        // qui change quand le fichier est copié
+        let start = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(timestamp_from_file_name)
+            .or_else(|| metadata.created().ok().and_then(to_millis));
+
+        recordings.push(Recording { path, start, end });
+    }
+
+    Ok(())
+}
+
+fn to_millis(time: SystemTime) -> Option<i64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_millis() as i64)
+}
+
+/// Extrait l'horodatage local d'un nom de fichier Outplayed, de la forme
+/// `League of Legends_10-18-2026_21-34-12-345.mp4` (MM-JJ-AAAA_HH-MM-SS).
+pub fn timestamp_from_file_name(file_name: &str) -> Option<i64> {
+    // "MM-DD-YYYY_HH-MM-SS" fait 19 caractères
+    (0..file_name.len().saturating_sub(18)).find_map(|i| {
+        let candidate = file_name.get(i..i + 19)?;
+        parse_outplayed_datetime(candidate)
+    })
+}
+
+fn parse_outplayed_datetime(s: &str) -> Option<i64> {
+    let pattern = "dd-dd-dddd_dd-dd-dd";
+    let matches_pattern = s
+        .chars()
+        .zip(pattern.chars())
+        .all(|(c, p)| if p == 'd' { c.is_ascii_digit() } else { c == p });
+    if !matches_pattern {
+        return None;
+    }
+
+    let month = s[0..2].parse().ok()?;
+    let day = s[3..5].parse().ok()?;
+    let year = s[6..10].parse().ok()?;
+    let hour = s[11..13].parse().ok()?;
+    let minute = s[14..16].parse().ok()?;
+    let second = s[17..19].parse().ok()?;
+
+    let naive: NaiveDateTime = NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, second)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME_START: i64 = 1_800_000_000_000;
+    const GAME_END: i64 = GAME_START + 30 * 60 * 1000;
+
+    fn recording(start: Option<i64>, end: i64) -> Recording {
+        Recording {
+            path: PathBuf::from("partie.mp4"),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn timestamp_from_outplayed_file_name() {
+        let expected = Local
+            .with_ymd_and_hms(2026, 10, 18, 21, 34, 12)
+            .earliest()
+            .unwrap()
+            .timestamp_millis();
+        assert_eq!(
+            timestamp_from_file_name("League of Legends_10-18-2026_21-34-12-345.mp4"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn timestamp_from_other_file_names() {
+        assert_eq!(timestamp_from_file_name("clip.mp4"), None);
+        assert_eq!(timestamp_from_file_name("League of Legends_13-45-2026_21-34-12.mp4"), None);
+        assert_eq!(timestamp_from_file_name("10-18-2026"), None);
+    }
+
+    #[test]
+    fn score_is_one_for_an_exact_match() {
+        assert_eq!(score(&recording(Some(GAME_START), GAME_END), GAME_START, GAME_END), 1.0);
+    }
+
+    #[test]
+    fn score_decreases_with_the_gap() {
+        let late_start = GAME_START + START_TOLERANCE_MS / 2;
+        assert_eq!(score(&recording(Some(late_start), GAME_END), GAME_START, GAME_END), 0.75);
+    }
+
+    #[test]
+    fn score_is_zero_beyond_a_tolerance() {
+        let late_end = GAME_END + END_TOLERANCE_MS;
+        assert_eq!(score(&recording(Some(GAME_START), late_end), GAME_START, GAME_END), 0.0);
+        let early_start = GAME_START - START_TOLERANCE_MS;
+        assert_eq!(score(&recording(Some(early_start), GAME_END), GAME_START, GAME_END), 0.0);
+    }
+
+    #[test]
+    fn score_without_start_is_capped() {
+        assert_eq!(score(&recording(None, GAME_END), GAME_START, GAME_END), 0.75);
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs;
+
+use crate::config::RetentionConfig;
+use crate::utils::audit::{append_audit, AuditAction, AuditEntry};
+
+/// Sort d'un enregistrement une fois son upload vérifié.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionPolicy {
+    #[default]
+    Keep,
+    Delete,
+    Archive,
+    // Déplacé dans la corbeille, puis supprimé après `trash_days` jours
+    Trash,
+}
+
+/// Applique la politique de rétention à un enregistrement dont l'upload a
+/// été vérifié, et consigne l'opération dans le journal d'audit.
+pub async fn apply_retention(
+    file: &Path,
+    config: &RetentionConfig,
+    match_id: &str,
+    video_id: &str,
+) -> Result<(), String> {
+    let size = match fs::metadata(file).await {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{} a déjà été retiré du dossier", file.display());
+            return Ok(());
+        }
+        Err(e) => return Err(format!("Impossible de lire {} : {}", file.display(), e)),
+    };
+
+    let (action, destination) = match config.policy {
+        RetentionPolicy::Keep => {
+            println!("Enregistrement conservé : {}", file.display());
+            return Ok(());
+        }
+        RetentionPolicy::Delete => {
+            fs::remove_file(file)
+                .await
+                .map_err(|e| format!("Impossible de supprimer {} : {}", file.display(), e))?;
+            (AuditAction::Deleted, None)
+        }
+        RetentionPolicy::Archive => {
+            let folder = config.archive_folder.as_deref().ok_or("retention.archive_folder non défini")?;
+            (AuditAction::Archived, Some(move_to(file, folder).await?))
+        }
+        RetentionPolicy::Trash => {
+            let folder = config.trash_folder.as_deref().ok_or("retention.trash_folder non défini")?;
+            let destination = move_to(file, folder).await?;
+            // La date de modification sert de date de mise à la corbeille pour l'expiration
+            File::options()
+                .write(true)
+                .open(&destination)
+                .and_then(|trashed| trashed.set_modified(SystemTime::now()))
+                .map_err(|e| format!("Impossible de dater {} : {}", destination.display(), e))?;
+            (AuditAction::Trashed, Some(destination))
+        }
+    };
+
+    match &destination {
+        Some(destination) => println!("{} déplacé vers {}", file.display(), destination.display()),
+        None => println!("{} supprimé", file.display()),
+    }
+    append_audit(&AuditEntry {
+        at: Utc::now(),
+        action,
+        file: file.to_path_buf(),
+        destination,
+        size,
+        match_id: Some(match_id.to_string()),
+        video_id: Some(video_id.to_string()),
+    })
+}
+
+/// Supprime les fichiers de la corbeille plus anciens que `trash_days` jours.
+pub async fn purge_trash(config: &RetentionConfig) -> Result<(), String> {
+    let Some(folder) = &config.trash_folder else {
+        return Ok(());
+    };
+    let mut entries = match fs::read_dir(folder).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Impossible de lire {} : {}", folder.display(), e)),
+    };
+    let expiry = SystemTime::from(Utc::now() - Duration::days(config.trash_days.into()));
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("Impossible de lire {} : {}", folder.display(), e))?
+    {
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let expired = metadata.is_file() && metadata.modified().is_ok_and(|modified| modified < expiry);
+        if !expired {
+            continue;
+        }
+
+        let path = entry.path();
+        if let Err(e) = fs::remove_file(&path).await {
+            eprintln!("Impossible de supprimer {} : {}", path.display(), e);
+            continue;
+        }
+        println!("{} supprimé de la corbeille", path.display());
+        append_audit(&AuditEntry {
+            at: Utc::now(),
+            action: AuditAction::Expired,
+            file: path,
+            destination: None,
+            size: metadata.len(),
+            match_id: None,
+            video_id: None,
+        })?;
+    }
+    Ok(())
+}
+
+// Déplace le fichier dans `folder` sans écraser un fichier du même nom
+async fn move_to(file: &Path, folder: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(folder)
+        .await
+        .map_err(|e| format!("Impossible de créer {} : {}", folder.display(), e))?;
+
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| format!("Chemin invalide : {}", file.display()))?;
+    let mut destination = folder.join(file_name);
+    let mut suffix = 1;
+    while fs::try_exists(&destination).await.unwrap_or(false) {
+        let stem = file.file_stem().unwrap_or(file_name).to_string_lossy();
+        let extension = file.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        destination = folder.join(format!("{} ({}){}", stem, suffix, extension));
+        suffix += 1;
+    }
+
+    // Un renommage échoue entre deux volumes : on copie puis on supprime l'original
+    if fs::rename(file, &destination).await.is_err() {
+        fs::copy(file, &destination)
+            .await
+            .map_err(|e| format!("Impossible de copier {} : {}", file.display(), e))?;
+        fs::remove_file(file)
+            .await
+            .map_err(|e| format!("Impossible de supprimer {} : {}", file.display(), e))?;
+    }
+    Ok(destination)
+}
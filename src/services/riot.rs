@@ -0,0 +1,175 @@
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::RwLock;
+use tokio::time::{sleep, Duration};
+
+use crate::models::types::Region;
+use crate::utils::rate_limit::RateLimiter;
+use crate::utils::redact::{redact, register_secret};
+
+const MAX_RETRIES: u32 = 3;
+// Délai utilisé quand une réponse 429 n'indique pas de Retry-After
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Client partagé pour l'API Riot : un seul pool de connexions et un seul
+/// limiteur de débit pour toutes les tâches.
+pub struct RiotClient {
+    http: reqwest::Client,
+    api_key: RwLock<String>,
+    limiter: RateLimiter,
+}
+
+impl RiotClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_key: RwLock::new(String::new()),
+            limiter: RateLimiter::new(),
+        }
+    }
+
+    /// Définit la clé API, à chaque chargement de la configuration. Retourne
+    /// la clé précédente, à restaurer si la configuration est rejetée.
+    pub fn set_api_key(&self, api_key: &str) -> String {
+        register_secret(api_key);
+        std::mem::replace(&mut *self.api_key.write().unwrap(), api_key.to_string())
+    }
+
+    /// Exécute une requête GET en respectant les limites de l'application et
+    /// de la méthode. Les réponses 429 sont réessayées après `Retry-After`.
+    pub async fn get(&self, region: Region, method: &str, url: Url) -> Result<Response, String> {
+        let api_key = self.api_key.read().unwrap().clone();
+
+        let app_key = region.host().to_string();
+        let method_key = format!("{}:{}", region.host(), method);
+
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire(&[&app_key, &method_key]).await;
+
+            let response = self
+                .http
+                .get(url.clone())
+                .header("X-Riot-Token", &api_key)
+                .send()
+                .await
+                .map_err(|e| redact(&e.to_string()))?;
+            self.update_limits(&app_key, &method_key, response.headers());
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            let retry_after = retry_after(response.headers()).unwrap_or(DEFAULT_RETRY_AFTER);
+            // Le type de limite atteinte indique qui doit patienter
+            match header_str(response.headers(), "X-Rate-Limit-Type") {
+                Some("method") => self.limiter.block(&method_key, retry_after),
+                _ => self.limiter.block(&app_key, retry_after),
+            }
+
+            attempt += 1;
+            if attempt > MAX_RETRIES {
+                return Err(format!(
+                    "Limite de requêtes Riot atteinte ({}), abandon après {} tentatives",
+                    method, attempt
+                ));
+            }
+            eprintln!(
+                "Limite de requêtes Riot atteinte ({}), nouvelle tentative dans {}s",
+                method,
+                retry_after.as_secs()
+            );
+            sleep(retry_after).await;
+        }
+    }
+
+    /// Comme `get`, mais vérifie le statut et désérialise le corps JSON.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        region: Region,
+        method: &str,
+        url: Url,
+    ) -> Result<T, RiotError> {
+        let response = self.get(region, method, url).await.map_err(RiotError::Unavailable)?;
+        let status = response.status();
+        // Une clé API refusée ou expirée ne dit rien de la requête elle-même
+        let key_rejected = status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+        if status.is_client_error() && !key_rejected {
+            return Err(RiotError::Rejected {
+                status,
+                message: format!("Erreur de l'API Riot ({}) : {}", method, status),
+            });
+        }
+        if !status.is_success() {
+            return Err(RiotError::Unavailable(format!(
+                "Erreur de l'API Riot ({}) : {}",
+                method, status
+            )));
+        }
+        response.json().await.map_err(|e| {
+            let message = redact(&e.to_string());
+            if e.is_decode() {
+                RiotError::Invalid(message)
+            } else {
+                RiotError::Unavailable(message)
+            }
+        })
+    }
+
+    fn update_limits(&self, app_key: &str, method_key: &str, headers: &HeaderMap) {
+        if let Some(limits) = header_str(headers, "X-App-Rate-Limit") {
+            self.limiter
+                .update(app_key, limits, header_str(headers, "X-App-Rate-Limit-Count"));
+        }
+        if let Some(limits) = header_str(headers, "X-Method-Rate-Limit") {
+            self.limiter
+                .update(method_key, limits, header_str(headers, "X-Method-Rate-Limit-Count"));
+        }
+    }
+}
+
+/// Erreur d'un appel à l'API Riot, selon qu'il est utile ou non de le refaire.
+#[derive(Debug)]
+pub enum RiotError {
+    // Requête refusée (4xx hors 401, 403 et 429) : elle échouera de la même façon
+    Rejected { status: StatusCode, message: String },
+    // Réponse reçue mais inexploitable
+    Invalid(String),
+    // Réseau, erreur serveur ou limite de débit : à réessayer plus tard
+    Unavailable(String),
+}
+
+impl RiotError {
+    pub fn is_permanent(&self) -> bool {
+        !matches!(self, RiotError::Unavailable(_))
+    }
+}
+
+impl fmt::Display for RiotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiotError::Rejected { message, .. } => write!(f, "{}", message),
+            RiotError::Invalid(message) | RiotError::Unavailable(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<RiotError> for String {
+    fn from(e: RiotError) -> Self {
+        e.to_string()
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_str(headers, "Retry-After")?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
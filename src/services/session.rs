@@ -0,0 +1,80 @@
+use std::fmt;
+use std::time::Duration;
+
+// Après la fin d'une partie, temps laissé pour que le match apparaisse dans
+// match-v5 et qu'Outplayed finisse l'enregistrement (voir RECORDING_GRACE_PERIOD_MS)
+pub const POST_GAME_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
+/// État de la session de jeu, qui pilote la boucle principale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    // Aucune partie en cours ni upload en attente
+    Idle,
+    InGame,
+    // Partie terminée : seul état où les comptes cherchent leurs matchs
+    PostGame,
+    // Des uploads sont en cours ou prêts à démarrer
+    Uploading,
+}
+
+impl GameState {
+    /// État suivant d'après les processus observés et la file d'upload.
+    /// L'après-partie dure `POST_GAME_TIMEOUT` même si un upload démarre, pour
+    /// laisser chaque compte présent dans la partie trouver son match.
+    pub fn next(self, game_running: bool, runnable_uploads: usize, elapsed: Duration) -> GameState {
+        if game_running {
+            return GameState::InGame;
+        }
+        match self {
+            GameState::InGame => GameState::PostGame,
+            GameState::PostGame if elapsed < POST_GAME_TIMEOUT => GameState::PostGame,
+            _ if runnable_uploads > 0 => GameState::Uploading,
+            _ => GameState::Idle,
+        }
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            GameState::Idle => "en attente",
+            GameState::InGame => "en partie",
+            GameState::PostGame => "après-partie",
+            GameState::Uploading => "upload",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JUST_NOW: Duration = Duration::from_secs(0);
+
+    #[test]
+    fn game_running_means_in_game() {
+        for state in [GameState::Idle, GameState::InGame, GameState::PostGame, GameState::Uploading] {
+            assert_eq!(state.next(true, 0, JUST_NOW), GameState::InGame);
+        }
+    }
+
+    #[test]
+    fn end_of_game_starts_post_game() {
+        assert_eq!(GameState::InGame.next(false, 0, JUST_NOW), GameState::PostGame);
+    }
+
+    #[test]
+    fn post_game_lasts_until_timeout() {
+        assert_eq!(GameState::PostGame.next(false, 1, JUST_NOW), GameState::PostGame);
+        assert_eq!(GameState::PostGame.next(false, 1, POST_GAME_TIMEOUT), GameState::Uploading);
+        assert_eq!(GameState::PostGame.next(false, 0, POST_GAME_TIMEOUT), GameState::Idle);
+    }
+
+    #[test]
+    fn uploads_drive_idle_and_uploading() {
+        assert_eq!(GameState::Idle.next(false, 2, JUST_NOW), GameState::Uploading);
+        assert_eq!(GameState::Uploading.next(false, 0, JUST_NOW), GameState::Idle);
+        assert_eq!(GameState::Idle.next(false, 0, JUST_NOW), GameState::Idle);
+    }
+}
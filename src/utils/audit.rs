@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+const AUDIT_PATH: &str = "retention_audit.jsonl";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Deleted,
+    Archived,
+    Trashed,
+    // Supprimé de la corbeille après expiration
+    Expired,
+    // Supprimé pour libérer de l'espace disque
+    Reclaimed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub action: AuditAction,
+    pub file: PathBuf,
+    pub destination: Option<PathBuf>,
+    pub size: u64,
+    pub match_id: Option<String>,
+    pub video_id: Option<String>,
+}
+
+/// Ajoute une ligne JSON au journal des fichiers déplacés ou supprimés.
+pub fn append_audit(entry: &AuditEntry) -> Result<(), String> {
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| format!("Erreur lors de la sérialisation de l'audit : {}", e))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_PATH)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Erreur lors de l'écriture de {} : {}", AUDIT_PATH, e))
+}
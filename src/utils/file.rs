@@ -0,0 +1,38 @@
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// Lit un fichier JSON d'état. Un fichier absent donne la valeur par défaut ;
+/// un fichier illisible ou corrompu est une erreur, pour ne pas l'écraser.
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Fichier {} corrompu : {}", path.display(), e))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Impossible de lire {} : {}", path.display(), e)),
+    }
+}
+
+/// Écrit un fichier de façon atomique : le contenu est écrit dans un fichier
+/// temporaire voisin, synchronisé sur le disque, puis renommé par-dessus la
+/// cible. Un arrêt brutal laisse donc soit l'ancien, soit le nouveau contenu.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let mut tmp_name = path
+        .file_name()
+        .ok_or_else(|| format!("Chemin invalide : {}", path.display()))?
+        .to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("Erreur lors de la création de {} : {}", tmp_path.display(), e))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Erreur lors de l'écriture de {} : {}", tmp_path.display(), e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Erreur lors du remplacement de {} : {}", path.display(), e))
+}
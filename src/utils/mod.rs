@@ -1 +1,9 @@
-pub mod storage;
\ No newline at end of file
+pub mod audit;
+pub mod file;
+pub mod playlist_cache;
+pub mod quota;
+pub mod rate_limit;
+pub mod redact;
+pub mod storage;
+pub mod upload_queue;
+pub mod upload_sessions;
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::utils::file::{read_json_or_default, write_atomic};
+
+const DEFAULT_CACHE_PATH: &str = "playlists.json";
+
+/// IDs des playlists YouTube par canal puis par titre, pour ne pas relister
+/// les playlists de la chaîne à chaque upload.
+#[derive(Debug)]
+pub struct PlaylistCache {
+    path: PathBuf,
+    playlists: HashMap<String, HashMap<String, String>>,
+}
+
+impl PlaylistCache {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(PathBuf::from(DEFAULT_CACHE_PATH))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, String> {
+        let playlists = read_json_or_default(&path)?;
+        Ok(Self { path, playlists })
+    }
+
+    pub fn get(&self, channel: &str, title: &str) -> Option<&String> {
+        self.playlists.get(channel)?.get(title)
+    }
+
+    pub fn insert(&mut self, channel: &str, title: String, playlist_id: String) -> Result<(), String> {
+        self.playlists
+            .entry(channel.to_string())
+            .or_default()
+            .insert(title, playlist_id);
+        self.save()
+    }
+
+    pub fn remove(&mut self, channel: &str, title: &str) -> Result<(), String> {
+        let removed = self
+            .playlists
+            .get_mut(channel)
+            .and_then(|playlists| playlists.remove(title));
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(&self.playlists)
+            .map_err(|e| format!("Erreur lors de la sérialisation des playlists : {}", e))?;
+        write_atomic(&self.path, &content)
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Days, NaiveDate, TimeZone, Utc};
+use chrono_tz::US::Pacific;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::utils::file::{read_json_or_default, write_atomic};
+
+const DEFAULT_QUOTA_PATH: &str = "youtube_quota.json";
+
+// Coût en unités des appels à l'API YouTube Data
+pub const VIDEO_INSERT_COST: u32 = 1600;
+pub const VIDEO_LIST_COST: u32 = 1;
+pub const PLAYLIST_LIST_COST: u32 = 1;
+pub const PLAYLIST_INSERT_COST: u32 = 50;
+pub const PLAYLIST_ITEM_INSERT_COST: u32 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DayUsage {
+    // Jour en heure du Pacifique : le quota est remis à zéro à minuit, heure de Californie
+    day: NaiveDate,
+    units: u32,
+    // YouTube a répondu quotaExceeded : plus aucun appel jusqu'à la remise à zéro
+    exhausted: bool,
+}
+
+/// Unités de quota YouTube consommées par projet Google et par jour,
+/// enregistrées sur disque pour rester justes après un redémarrage.
+#[derive(Debug)]
+pub struct QuotaTracker {
+    path: PathBuf,
+    projects: HashMap<String, DayUsage>,
+}
+
+impl QuotaTracker {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(PathBuf::from(DEFAULT_QUOTA_PATH))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, String> {
+        let projects = read_json_or_default(&path)?;
+        Ok(Self { path, projects })
+    }
+
+    /// Réserve `units` si le budget du jour le permet. Retourne faux sinon,
+    /// sans rien consommer.
+    pub fn try_reserve(&mut self, project: &str, units: u32, daily_limit: u32) -> Result<bool, String> {
+        let usage = self.today(project);
+        if usage.exhausted || usage.units + units > daily_limit {
+            return Ok(false);
+        }
+        usage.units += units;
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Comptabilise des unités déjà dépensées, même au-delà du budget.
+    pub fn record(&mut self, project: &str, units: u32) -> Result<(), String> {
+        let usage = self.today(project);
+        usage.units = usage.units.saturating_add(units);
+        self.save()
+    }
+
+    pub fn mark_exhausted(&mut self, project: &str) -> Result<(), String> {
+        self.today(project).exhausted = true;
+        self.save()
+    }
+
+    pub fn used_today(&mut self, project: &str) -> u32 {
+        self.today(project).units
+    }
+
+    fn today(&mut self, project: &str) -> &mut DayUsage {
+        let day = pacific_day(Utc::now());
+        let usage = self.projects.entry(project.to_string()).or_insert(DayUsage {
+            day,
+            units: 0,
+            exhausted: false,
+        });
+        if usage.day != day {
+            *usage = DayUsage {
+                day,
+                units: 0,
+                exhausted: false,
+            };
+        }
+        usage
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(&self.projects)
+            .map_err(|e| format!("Erreur lors de la sérialisation du quota : {}", e))?;
+        write_atomic(&self.path, &content)
+    }
+}
+
+fn pacific_day(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&Pacific).date_naive()
+}
+
+/// Prochaine remise à zéro du quota : minuit suivant, heure du Pacifique.
+pub fn next_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let tomorrow = pacific_day(now) + Days::new(1);
+    Pacific
+        .from_local_datetime(&tomorrow.and_time(Default::default()))
+        .earliest()
+        .map(|reset| reset.with_timezone(&Utc))
+        // Minuit inexistant dans ce fuseau (jamais en pratique) : nouvel essai dans 24 h
+        .unwrap_or_else(|| now + chrono::Duration::hours(24))
+}
+
+/// Reconnaît une erreur de quota dans le corps d'une réponse de l'API YouTube.
+pub fn is_quota_exceeded(body: &str) -> bool {
+    let Ok(error) = serde_json::from_str::<Value>(body) else {
+        return false;
+    };
+    error["error"]["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|e| e["reason"].as_str() == Some("quotaExceeded"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn next_reset_is_pacific_midnight_in_summer() {
+        assert_eq!(next_reset(utc("2026-10-18T12:00:00Z")), utc("2026-10-19T07:00:00Z"));
+    }
+
+    #[test]
+    fn next_reset_is_pacific_midnight_in_winter() {
+        assert_eq!(next_reset(utc("2026-12-01T10:00:00Z")), utc("2026-12-02T08:00:00Z"));
+    }
+
+    #[test]
+    fn next_reset_just_before_midnight() {
+        assert_eq!(next_reset(utc("2026-10-19T06:59:59Z")), utc("2026-10-19T07:00:00Z"));
+        assert_eq!(next_reset(utc("2026-10-19T07:00:00Z")), utc("2026-10-20T07:00:00Z"));
+    }
+
+    #[test]
+    fn next_reset_on_daylight_saving_change() {
+        // Fin de l'heure d'été le 1er novembre 2026
+        assert_eq!(next_reset(utc("2026-11-01T12:00:00Z")), utc("2026-11-02T08:00:00Z"));
+    }
+}
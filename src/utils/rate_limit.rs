@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
+
+// Limites d'une clé de développement, utilisées tant que l'API ne nous a pas
+// renvoyé les vraies limites dans ses en-têtes
+const DEFAULT_LIMITS: &str = "20:1,100:120";
+
+#[derive(Debug)]
+struct Bucket {
+    limit: u32,
+    window: Duration,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn new(limit: u32, window: Duration, now: Instant) -> Self {
+        Self {
+            limit,
+            window,
+            tokens: limit as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let rate = self.limit as f64 / self.window.as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(self.limit as f64);
+        self.last_refill = now;
+    }
+
+    // Temps à attendre avant qu'un jeton soit disponible
+    fn wait_time(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        let rate = self.limit as f64 / self.window.as_secs_f64();
+        Duration::from_secs_f64((1.0 - self.tokens) / rate)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: HashMap<String, Vec<Bucket>>,
+    blocked_until: HashMap<String, Instant>,
+}
+
+/// Limiteur à seaux de jetons partagé entre toutes les tâches. Chaque clé
+/// (application ou méthode d'un host donné) possède un seau par fenêtre de
+/// limite, ajustés à partir des en-têtes `X-*-Rate-Limit` de Riot.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attend qu'un jeton soit disponible pour toutes les clés, puis le consomme.
+    pub async fn acquire(&self, keys: &[&str]) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let mut wait = Duration::ZERO;
+
+                for key in keys {
+                    if let Some(until) = state.blocked_until.get(*key) {
+                        wait = wait.max(until.saturating_duration_since(now));
+                    }
+                    let buckets = state
+                        .buckets
+                        .entry(key.to_string())
+                        .or_insert_with(|| parse_buckets(DEFAULT_LIMITS, now));
+                    for bucket in buckets.iter_mut() {
+                        bucket.refill(now);
+                        wait = wait.max(bucket.wait_time());
+                    }
+                }
+
+                if wait.is_zero() {
+                    for key in keys {
+                        if let Some(buckets) = state.buckets.get_mut(*key) {
+                            buckets.iter_mut().for_each(|bucket| bucket.tokens -= 1.0);
+                        }
+                    }
+                    return;
+                }
+                wait
+            };
+            sleep(wait).await;
+        }
+    }
+
+    /// Met à jour les seaux d'une clé à partir des en-têtes de limite
+    /// (`20:1,100:120`) et de compteur (`1:1,1:120`) renvoyés par l'API.
+    pub fn update(&self, key: &str, limits: &str, counts: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let parsed = parse_pairs(limits);
+        if parsed.is_empty() {
+            return;
+        }
+        let buckets = state.buckets.entry(key.to_string()).or_default();
+        let unchanged = buckets.len() == parsed.len()
+            && buckets
+                .iter()
+                .zip(&parsed)
+                .all(|(bucket, (limit, window))| {
+                    bucket.limit == *limit && bucket.window.as_secs() == *window
+                });
+        if !unchanged {
+            *buckets = parsed
+                .iter()
+                .map(|(limit, window)| Bucket::new(*limit, Duration::from_secs(*window), now))
+                .collect();
+        }
+
+        // Le compteur côté serveur fait foi s'il est plus pessimiste que le nôtre
+        for (count, window) in counts.map(parse_pairs).unwrap_or_default() {
+            if let Some(bucket) = buckets.iter_mut().find(|b| b.window.as_secs() == window) {
+                bucket.refill(now);
+                let remaining = bucket.limit.saturating_sub(count) as f64;
+                bucket.tokens = bucket.tokens.min(remaining);
+            }
+        }
+    }
+
+    /// Bloque toutes les requêtes d'une clé jusqu'à l'expiration du délai.
+    pub fn block(&self, key: &str, delay: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let until = Instant::now() + delay;
+        let entry = state.blocked_until.entry(key.to_string()).or_insert(until);
+        *entry = (*entry).max(until);
+    }
+}
+
+fn parse_buckets(limits: &str, now: Instant) -> Vec<Bucket> {
+    parse_pairs(limits)
+        .into_iter()
+        .map(|(limit, window)| Bucket::new(limit, Duration::from_secs(window), now))
+        .collect()
+}
+
+// Analyse une liste `valeur:fenêtre` séparée par des virgules
+fn parse_pairs(header: &str) -> Vec<(u32, u64)> {
+    header
+        .split(',')
+        .filter_map(|pair| {
+            let (value, window) = pair.trim().split_once(':')?;
+            let value = value.parse().ok()?;
+            let window = window.parse().ok().filter(|w| *w > 0)?;
+            Some((value, window))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pairs_reads_limit_headers() {
+        assert_eq!(parse_pairs("20:1,100:120"), vec![(20, 1), (100, 120)]);
+        assert_eq!(parse_pairs(" 1:1 , 3:120"), vec![(1, 1), (3, 120)]);
+    }
+
+    #[test]
+    fn parse_pairs_skips_malformed_entries() {
+        assert_eq!(parse_pairs("20:1,abc,5:0,7:x,3:10"), vec![(20, 1), (3, 10)]);
+        assert!(parse_pairs("").is_empty());
+    }
+
+    #[test]
+    fn bucket_waits_once_empty() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(2, Duration::from_secs(1), now);
+        assert_eq!(bucket.wait_time(), Duration::ZERO);
+
+        bucket.tokens -= 2.0;
+        assert_eq!(bucket.wait_time(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn bucket_refills_up_to_its_limit() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(2, Duration::from_secs(1), now);
+        bucket.tokens = 0.0;
+
+        bucket.refill(now + Duration::from_millis(500));
+        assert!((bucket.tokens - 1.0).abs() < 1e-9);
+
+        bucket.refill(now + Duration::from_secs(10));
+        assert_eq!(bucket.tokens, 2.0);
+    }
+}
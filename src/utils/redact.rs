@@ -0,0 +1,95 @@
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+const MASK: &str = "***";
+// Les secrets trop courts masqueraient des morceaux de texte ordinaires
+const MIN_SECRET_LEN: usize = 8;
+// Préfixes de jetons reconnaissables même s'ils n'ont pas été enregistrés :
+// clés Riot, jetons d'accès et d'actualisation Google
+const TOKEN_PREFIXES: [&str; 3] = ["RGAPI-", "ya29.", "1//"];
+// Paramètres d'URL dont la valeur est toujours masquée
+const SENSITIVE_PARAMS: [&str; 3] = ["api_key=", "access_token=", "refresh_token="];
+
+lazy_static! {
+    static ref SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+/// Enregistre une valeur qui ne doit jamais apparaître dans les logs.
+pub fn register_secret(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap();
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Masque les secrets enregistrés, les jetons reconnaissables et les
+/// paramètres d'URL sensibles d'un texte destiné aux logs.
+pub fn redact(text: &str) -> String {
+    let mut result = text.to_string();
+    for secret in SECRETS.read().unwrap().iter() {
+        result = result.replace(secret.as_str(), MASK);
+    }
+    for prefix in TOKEN_PREFIXES {
+        result = mask_after(&result, prefix);
+    }
+    for param in SENSITIVE_PARAMS {
+        result = mask_after(&result, param);
+    }
+    result
+}
+
+// Remplace la valeur qui suit chaque occurrence de `marker`
+fn mask_after(text: &str, marker: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(marker) {
+        let (before, after) = rest.split_at(pos + marker.len());
+        result.push_str(before);
+        let value_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "-_./~+".contains(c)))
+            .unwrap_or(after.len());
+        if value_len > 0 {
+            result.push_str(MASK);
+        }
+        rest = &after[value_len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_after_hides_the_value_only() {
+        assert_eq!(
+            mask_after("https://x/?api_key=RGAPI-abc-123&count=1", "api_key="),
+            "https://x/?api_key=***&count=1"
+        );
+    }
+
+    #[test]
+    fn mask_after_handles_every_occurrence() {
+        assert_eq!(
+            mask_after("access_token=abc access_token=def.ghi", "access_token="),
+            "access_token=*** access_token=***"
+        );
+    }
+
+    #[test]
+    fn mask_after_leaves_empty_values() {
+        assert_eq!(mask_after("api_key=&count=1", "api_key="), "api_key=&count=1");
+        assert_eq!(mask_after("aucun secret", "api_key="), "aucun secret");
+    }
+
+    #[test]
+    fn redact_masks_recognizable_tokens() {
+        assert_eq!(redact("Bearer ya29.a0AfB_xyz-1"), "Bearer ya29.***");
+        assert_eq!(redact("clé RGAPI-0000-1111 refusée"), "clé RGAPI-*** refusée");
+    }
+}
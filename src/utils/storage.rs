@@ -1,22 +1,126 @@
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 
+use crate::utils::file::{read_json_or_default, write_atomic};
+
+const DEFAULT_STORAGE_PATH: &str = "match_storage.json";
+// Nombre de matchs traités conservés par compte pour détecter les doublons
+const MAX_PROCESSED_PER_ACCOUNT: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastMatch {
+    pub match_id: String,
+    // Début de la partie, en secondes depuis l'epoch (paramètre startTime de match-v5)
+    pub game_start: i64,
+}
+
+/// Enregistrement dont l'upload a été vérifié : il peut être supprimé sans perte.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadedRecording {
+    pub match_id: String,
+    pub file: PathBuf,
+    pub video_id: String,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AccountMatches {
+    last_match: Option<LastMatch>,
+    #[serde(default)]
+    processed: VecDeque<String>,
+    // Enregistrements uploadés encore présents dans folder_path
+    #[serde(default)]
+    uploaded: Vec<UploadedRecording>,
+}
+
+/// Matchs traités par PUUID, enregistrés sur disque à chaque modification
+/// pour reprendre exactement au même point après un redémarrage.
 #[derive(Debug)]
 pub struct MatchStorage {
-    storage: HashMap<String, String>,
+    path: PathBuf,
+    storage: HashMap<String, AccountMatches>,
 }
 
 impl MatchStorage {
-    pub fn new() -> Self {
-        Self {
-            storage: HashMap::new(),
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(PathBuf::from(DEFAULT_STORAGE_PATH))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, String> {
+        let storage = read_json_or_default(&path)?;
+        Ok(Self { path, storage })
+    }
+
+    pub fn last_match(&self, puuid: &str) -> Option<&LastMatch> {
+        self.storage.get(puuid)?.last_match.as_ref()
+    }
+
+    pub fn is_processed(&self, puuid: &str, match_id: &str) -> bool {
+        self.storage
+            .get(puuid)
+            .is_some_and(|account| account.processed.iter().any(|id| id == match_id))
+    }
+
+    /// Enregistre le point de départ d'un compte sans marquer de match comme traité.
+    pub fn store_baseline(&mut self, puuid: String, match_id: String, game_start: i64) -> Result<(), String> {
+        let account = self.storage.entry(puuid).or_default();
+        account.last_match = Some(LastMatch {
+            match_id,
+            game_start,
+        });
+        self.save()
+    }
+
+    pub fn store_match(&mut self, puuid: String, match_id: String, game_start: i64) -> Result<(), String> {
+        let account = self.storage.entry(puuid).or_default();
+        account.processed.push_back(match_id.clone());
+        while account.processed.len() > MAX_PROCESSED_PER_ACCOUNT {
+            account.processed.pop_front();
         }
+        account.last_match = Some(LastMatch {
+            match_id,
+            game_start,
+        });
+        self.save()
     }
 
-    pub fn is_match_id_known(&self, puuid: &str, match_id: &str) -> bool {
-        self.storage.get(puuid).map_or(false, |id| id == match_id)
+    pub fn record_upload(&mut self, puuid: String, recording: UploadedRecording) -> Result<(), String> {
+        let account = self.storage.entry(puuid).or_default();
+        account.uploaded.retain(|uploaded| uploaded.file != recording.file);
+        account.uploaded.push(recording);
+        self.save()
+    }
+
+    /// Enregistrements uploadés de tous les comptes, les plus anciens en premier.
+    pub fn uploaded_recordings(&self) -> Vec<UploadedRecording> {
+        let mut recordings: Vec<UploadedRecording> = self
+            .storage
+            .values()
+            .flat_map(|account| account.uploaded.iter().cloned())
+            .collect();
+        recordings.sort_by_key(|recording| recording.uploaded_at);
+        recordings
+    }
+
+    /// Oublie un enregistrement qui n'est plus dans folder_path.
+    pub fn forget_upload(&mut self, file: &Path) -> Result<(), String> {
+        let mut removed = false;
+        for account in self.storage.values_mut() {
+            let before = account.uploaded.len();
+            account.uploaded.retain(|uploaded| uploaded.file != file);
+            removed |= account.uploaded.len() != before;
+        }
+        if removed {
+            self.save()?;
+        }
+        Ok(())
     }
 
-    pub fn store_match_id(&mut self, puuid: String, match_id: String) {
-        self.storage.insert(puuid, match_id);
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(&self.storage)
+            .map_err(|e| format!("Erreur lors de la sérialisation du storage : {}", e))?;
+        write_atomic(&self.path, &content)
     }
 }
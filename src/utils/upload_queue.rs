@@ -0,0 +1,460 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::services::metadata::VideoMetadata;
+use crate::utils::file::{read_json_or_default, write_atomic};
+
+const DEFAULT_QUEUE_PATH: &str = "upload_queue.json";
+// Au-delà, le job passe en échec définitif
+const MAX_ATTEMPTS: u32 = 5;
+// Délai avant un nouvel essai : doublé à chaque échec, plafonné
+const RETRY_BASE_DELAY_SECS: i64 = 60;
+const RETRY_MAX_DELAY_SECS: i64 = 60 * 60;
+// Jobs terminés (réussis ou en échec) conservés pour l'historique
+const MAX_FINISHED_JOBS: usize = 200;
+// Au-delà, une vidéo toujours en traitement chez YouTube n'est plus attendue
+const MAX_PROCESSING_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    // En attente d'un worker
+    Pending,
+    // Vidéo en cours d'envoi
+    Uploading,
+    // Vidéo en ligne, en attente d'un worker pour les étapes suivantes
+    Processing,
+    // Vidéo en ligne : ajout aux playlists et nettoyage de l'enregistrement en cours
+    Verifying,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadJob {
+    pub id: String,
+    pub match_id: String,
+    pub puuid: String,
+    // Nom du compte pour les logs
+    pub account: String,
+    pub channel: String,
+    pub file: PathBuf,
+    pub video: VideoMetadata,
+    // Titres des playlists calculés à la détection du match
+    pub playlists: Vec<String>,
+    // Playlists auxquelles la vidéo a déjà été ajoutée : un nouvel essai ne
+    // traite que les autres
+    #[serde(default)]
+    pub added_playlists: Vec<String>,
+    pub state: JobState,
+    pub attempts: u32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    // Connu dès que l'upload a abouti : un nouvel essai ne renvoie pas la vidéo
+    pub video_id: Option<String>,
+    // Fin de l'envoi, point de départ de l'attente du traitement YouTube
+    #[serde(default)]
+    pub uploaded_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UploadJob {
+    pub fn new(
+        match_id: String,
+        puuid: String,
+        account: String,
+        channel: String,
+        file: PathBuf,
+        video: VideoMetadata,
+        playlists: Vec<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: format!("{}:{}", puuid, match_id),
+            match_id,
+            puuid,
+            account,
+            channel,
+            file,
+            video,
+            playlists,
+            added_playlists: Vec::new(),
+            state: JobState::Pending,
+            attempts: 0,
+            next_attempt_at: None,
+            video_id: None,
+            uploaded_at: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Playlists auxquelles la vidéo reste à ajouter.
+    pub fn missing_playlists(&self) -> Vec<String> {
+        self.playlists
+            .iter()
+            .filter(|title| !self.added_playlists.contains(title))
+            .cloned()
+            .collect()
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.state, JobState::Done | JobState::Failed)
+    }
+
+    // Un job non terminé qu'aucun worker ne traite reprend là où il s'était arrêté
+    fn waiting_state(&self) -> JobState {
+        if self.video_id.is_some() {
+            JobState::Processing
+        } else {
+            JobState::Pending
+        }
+    }
+}
+
+/// File d'attente des uploads, enregistrée sur disque à chaque changement
+/// d'état pour survivre aux redémarrages.
+#[derive(Debug)]
+pub struct UploadQueue {
+    path: PathBuf,
+    jobs: Vec<UploadJob>,
+}
+
+impl UploadQueue {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(PathBuf::from(DEFAULT_QUEUE_PATH))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, String> {
+        let mut jobs: Vec<UploadJob> = read_json_or_default(&path)?;
+        // Jobs interrompus par l'arrêt du programme : l'upload reprendra via sa session
+        for job in jobs
+            .iter_mut()
+            .filter(|job| matches!(job.state, JobState::Uploading | JobState::Verifying))
+        {
+            job.state = job.waiting_state();
+        }
+        Ok(Self { path, jobs })
+    }
+
+    /// Ajoute un job, sauf s'il existe déjà. Retourne vrai s'il a été ajouté.
+    pub fn enqueue(&mut self, job: UploadJob) -> Result<bool, String> {
+        if self.jobs.iter().any(|existing| existing.id == job.id) {
+            return Ok(false);
+        }
+        self.jobs.push(job);
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Réserve le plus ancien job prêt à être traité.
+    pub fn claim_next(&mut self) -> Result<Option<UploadJob>, String> {
+        let now = Utc::now();
+        let Some(job) = self.jobs.iter_mut().find(|job| {
+            matches!(job.state, JobState::Pending | JobState::Processing)
+                && job.next_attempt_at.is_none_or(|at| at <= now)
+        }) else {
+            return Ok(None);
+        };
+
+        // Le job reste réservé jusqu'à son prochain changement d'état : un autre
+        // worker ne peut pas le prendre en parallèle
+        job.state = if job.video_id.is_some() {
+            JobState::Verifying
+        } else {
+            JobState::Uploading
+        };
+        job.next_attempt_at = None;
+        job.updated_at = now;
+        let job = job.clone();
+        self.save()?;
+        Ok(Some(job))
+    }
+
+    /// Nombre de jobs en cours ou prêts à l'être ; les jobs reportés (quota
+    /// épuisé, nouvel essai programmé) ne sont pas comptés.
+    pub fn runnable_jobs(&self) -> usize {
+        let now = Utc::now();
+        self.jobs
+            .iter()
+            .filter(|job| match job.state {
+                JobState::Uploading | JobState::Verifying => true,
+                JobState::Pending | JobState::Processing => job.next_attempt_at.is_none_or(|at| at <= now),
+                JobState::Done | JobState::Failed => false,
+            })
+            .count()
+    }
+
+    /// Date du prochain job en attente d'un nouvel essai, pour réveiller les workers.
+    pub fn next_retry_at(&self) -> Option<DateTime<Utc>> {
+        self.jobs
+            .iter()
+            .filter(|job| matches!(job.state, JobState::Pending | JobState::Processing))
+            .filter_map(|job| job.next_attempt_at)
+            .min()
+    }
+
+    /// Enregistre l'ID de la vidéo envoyée ; le job reste réservé par son worker.
+    pub fn mark_uploaded(&mut self, id: &str, video_id: String) -> Result<(), String> {
+        self.update(id, |job| {
+            job.state = JobState::Verifying;
+            job.video_id = Some(video_id);
+            job.uploaded_at = Some(Utc::now());
+        })
+    }
+
+    pub fn mark_added_to_playlists(&mut self, id: &str, titles: Vec<String>) -> Result<(), String> {
+        self.update(id, |job| {
+            for title in titles {
+                if !job.added_playlists.contains(&title) {
+                    job.added_playlists.push(title);
+                }
+            }
+        })
+    }
+
+    pub fn mark_done(&mut self, id: &str) -> Result<(), String> {
+        self.update(id, |job| {
+            job.state = JobState::Done;
+            job.last_error = None;
+        })
+    }
+
+    /// Enregistre un échec : le job sera réessayé plus tard, ou abandonné
+    /// après `MAX_ATTEMPTS` essais. Retourne le nouvel état.
+    pub fn mark_failed(&mut self, id: &str, error: String) -> Result<JobState, String> {
+        let mut state = JobState::Failed;
+        self.update(id, |job| {
+            job.attempts += 1;
+            job.last_error = Some(error);
+            if job.attempts >= MAX_ATTEMPTS {
+                job.state = JobState::Failed;
+                job.next_attempt_at = None;
+            } else {
+                let delay = (RETRY_BASE_DELAY_SECS << (job.attempts - 1)).min(RETRY_MAX_DELAY_SECS);
+                job.state = job.waiting_state();
+                job.next_attempt_at = Some(Utc::now() + Duration::seconds(delay));
+            }
+            state = job.state;
+        })?;
+        Ok(state)
+    }
+
+    /// Reporte un job sans compter d'échec (ex. quota YouTube épuisé).
+    pub fn defer(&mut self, id: &str, until: DateTime<Utc>) -> Result<(), String> {
+        self.update(id, |job| {
+            job.state = job.waiting_state();
+            job.next_attempt_at = Some(until);
+        })
+    }
+
+    /// Reporte la vérification d'une vidéo encore en traitement chez YouTube.
+    /// Le job est abandonné si le traitement dure plus de `MAX_PROCESSING_HOURS`.
+    /// Retourne le nouvel état.
+    pub fn defer_processing(&mut self, id: &str, until: DateTime<Utc>) -> Result<JobState, String> {
+        let mut state = JobState::Failed;
+        self.update(id, |job| {
+            // Les jobs enregistrés avant `uploaded_at` partent de leur création
+            let uploaded_at = job.uploaded_at.unwrap_or(job.created_at);
+            if Utc::now() - uploaded_at >= Duration::hours(MAX_PROCESSING_HOURS) {
+                job.state = JobState::Failed;
+                job.next_attempt_at = None;
+                job.last_error = Some(format!(
+                    "vidéo toujours en traitement par YouTube après {} h",
+                    MAX_PROCESSING_HOURS
+                ));
+            } else {
+                job.state = job.waiting_state();
+                job.next_attempt_at = Some(until);
+            }
+            state = job.state;
+        })?;
+        Ok(state)
+    }
+
+    /// Remet en attente un job interrompu (arrêt du programme) sans compter d'échec.
+    pub fn release(&mut self, id: &str) -> Result<(), String> {
+        self.update(id, |job| {
+            if matches!(job.state, JobState::Uploading | JobState::Verifying) {
+                job.state = job.waiting_state();
+            }
+        })
+    }
+
+    fn update(&mut self, id: &str, change: impl FnOnce(&mut UploadJob)) -> Result<(), String> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| format!("Job d'upload inconnu : {}", id))?;
+        change(job);
+        job.updated_at = Utc::now();
+        self.prune();
+        self.save()
+    }
+
+    // Oublie les plus anciens jobs terminés
+    fn prune(&mut self) {
+        let finished = self.jobs.iter().filter(|job| job.is_finished()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        self.jobs.retain(|job| {
+            if excess > 0 && job.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(&self.jobs)
+            .map_err(|e| format!("Erreur lors de la sérialisation de la file d'upload : {}", e))?;
+        write_atomic(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(match_id: &str) -> UploadJob {
+        UploadJob::new(
+            match_id.to_string(),
+            "puuid".to_string(),
+            "Compte#EUW".to_string(),
+            "chaine".to_string(),
+            PathBuf::from(format!("{}.mp4", match_id)),
+            VideoMetadata {
+                title: "Titre".to_string(),
+                description: String::new(),
+                tags: Vec::new(),
+            },
+            vec!["Ahri".to_string(), "Rôle MID".to_string(), "Saison 2026".to_string()],
+        )
+    }
+
+    #[test]
+    fn missing_playlists_skips_added_ones() {
+        let mut job = job("EUW1_1");
+        assert_eq!(job.missing_playlists(), job.playlists);
+
+        job.added_playlists = vec!["Rôle MID".to_string()];
+        assert_eq!(job.missing_playlists(), vec!["Ahri".to_string(), "Saison 2026".to_string()]);
+    }
+
+    // File vide dans un fichier propre au test, effacé s'il existait
+    fn queue(name: &str) -> UploadQueue {
+        let path = std::env::temp_dir().join(format!("upload_queue_{}_{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        UploadQueue::load_from(path).unwrap()
+    }
+
+    fn state(queue: &UploadQueue, id: &str) -> JobState {
+        queue.jobs.iter().find(|job| job.id == id).unwrap().state
+    }
+
+    #[test]
+    fn claimed_job_is_not_claimed_twice() {
+        let mut queue = queue("claim");
+        queue.enqueue(job("EUW1_1")).unwrap();
+
+        let claimed = queue.claim_next().unwrap().unwrap();
+        assert_eq!(claimed.state, JobState::Uploading);
+        assert!(queue.claim_next().unwrap().is_none());
+
+        // Vidéo envoyée : le job reste réservé pendant la suite du traitement
+        queue.mark_uploaded(&claimed.id, "video".to_string()).unwrap();
+        assert_eq!(state(&queue, &claimed.id), JobState::Verifying);
+        assert!(queue.claim_next().unwrap().is_none());
+        assert_eq!(queue.runnable_jobs(), 1);
+    }
+
+    #[test]
+    fn deferred_job_waits_then_resumes_without_upload() {
+        let mut queue = queue("defer");
+        queue.enqueue(job("EUW1_1")).unwrap();
+        let id = queue.claim_next().unwrap().unwrap().id;
+        queue.mark_uploaded(&id, "video".to_string()).unwrap();
+
+        queue.defer(&id, Utc::now() + Duration::minutes(5)).unwrap();
+        assert_eq!(state(&queue, &id), JobState::Processing);
+        assert!(queue.claim_next().unwrap().is_none());
+        assert_eq!(queue.runnable_jobs(), 0);
+
+        queue.defer(&id, Utc::now()).unwrap();
+        let claimed = queue.claim_next().unwrap().unwrap();
+        assert_eq!(claimed.state, JobState::Verifying);
+        assert_eq!(claimed.next_attempt_at, None);
+        assert!(queue.claim_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn processing_is_abandoned_after_deadline() {
+        let mut queue = queue("processing");
+        queue.enqueue(job("EUW1_1")).unwrap();
+        let id = queue.claim_next().unwrap().unwrap().id;
+        queue.mark_uploaded(&id, "video".to_string()).unwrap();
+
+        let state = queue.defer_processing(&id, Utc::now() + Duration::minutes(5)).unwrap();
+        assert_eq!(state, JobState::Processing);
+
+        queue.jobs[0].uploaded_at = Some(Utc::now() - Duration::hours(MAX_PROCESSING_HOURS));
+        let state = queue.defer_processing(&id, Utc::now() + Duration::minutes(5)).unwrap();
+        assert_eq!(state, JobState::Failed);
+        assert!(queue.jobs[0].last_error.is_some());
+        assert_eq!(queue.runnable_jobs(), 0);
+    }
+
+    #[test]
+    fn failed_job_is_retried_then_abandoned() {
+        let mut queue = queue("failed");
+        queue.enqueue(job("EUW1_1")).unwrap();
+        let id = queue.claim_next().unwrap().unwrap().id;
+
+        let state = queue.mark_failed(&id, "erreur".to_string()).unwrap();
+        assert_eq!(state, JobState::Pending);
+        // Le nouvel essai est programmé plus tard
+        assert!(queue.claim_next().unwrap().is_none());
+
+        for _ in 1..MAX_ATTEMPTS - 1 {
+            queue.mark_failed(&id, "erreur".to_string()).unwrap();
+        }
+        let state = queue.mark_failed(&id, "erreur".to_string()).unwrap();
+        assert_eq!(state, JobState::Failed);
+        assert_eq!(queue.runnable_jobs(), 0);
+    }
+
+    #[test]
+    fn released_job_can_be_claimed_again() {
+        let mut queue = queue("release");
+        queue.enqueue(job("EUW1_1")).unwrap();
+        queue.enqueue(job("EUW1_2")).unwrap();
+        let first = queue.claim_next().unwrap().unwrap().id;
+        let second = queue.claim_next().unwrap().unwrap().id;
+        queue.mark_uploaded(&second, "video".to_string()).unwrap();
+
+        queue.release(&first).unwrap();
+        queue.release(&second).unwrap();
+        assert_eq!(state(&queue, &first), JobState::Pending);
+        assert_eq!(state(&queue, &second), JobState::Processing);
+        assert_eq!(queue.claim_next().unwrap().unwrap().id, first);
+    }
+
+    #[test]
+    fn reload_releases_interrupted_jobs() {
+        let mut queue = queue("reload");
+        queue.enqueue(job("EUW1_1")).unwrap();
+        queue.enqueue(job("EUW1_2")).unwrap();
+        let first = queue.claim_next().unwrap().unwrap().id;
+        let second = queue.claim_next().unwrap().unwrap().id;
+        queue.mark_uploaded(&second, "video".to_string()).unwrap();
+
+        let queue = UploadQueue::load_from(queue.path.clone()).unwrap();
+        assert_eq!(state(&queue, &first), JobState::Pending);
+        assert_eq!(state(&queue, &second), JobState::Processing);
+        let _ = std::fs::remove_file(&queue.path);
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::utils::file::{read_json_or_default, write_atomic};
+
+const DEFAULT_SESSIONS_PATH: &str = "upload_sessions.json";
+// YouTube garde une session résumable environ une semaine ; au-delà on en
+// recrée une sans même interroger le serveur
+const MAX_SESSION_AGE_DAYS: i64 = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub file: PathBuf,
+    pub size: u64,
+    pub channel: String,
+    pub session_uri: String,
+    // Octets confirmés par le serveur au dernier morceau accepté
+    pub confirmed_offset: u64,
+    pub metadata: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UploadSession {
+    /// Vrai si la session a été ouverte pour exactement ce fichier et ces métadonnées.
+    pub fn matches(&self, size: u64, channel: &str, metadata: &Value) -> bool {
+        self.size == size
+            && self.channel == channel
+            && &self.metadata == metadata
+            && Utc::now() - self.created_at < Duration::days(MAX_SESSION_AGE_DAYS)
+    }
+}
+
+/// Sessions d'upload en cours, indexées par chemin de fichier et enregistrées
+/// sur disque pour reprendre un upload interrompu après un redémarrage.
+#[derive(Debug)]
+pub struct UploadSessions {
+    path: PathBuf,
+    sessions: HashMap<String, UploadSession>,
+}
+
+impl UploadSessions {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(PathBuf::from(DEFAULT_SESSIONS_PATH))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, String> {
+        let sessions = read_json_or_default(&path)?;
+        Ok(Self { path, sessions })
+    }
+
+    pub fn get(&self, file: &Path) -> Option<&UploadSession> {
+        self.sessions.get(&key(file))
+    }
+
+    pub fn insert(&mut self, session: UploadSession) -> Result<(), String> {
+        self.sessions.insert(key(&session.file), session);
+        self.save()
+    }
+
+    pub fn update_offset(&mut self, file: &Path, confirmed_offset: u64) -> Result<(), String> {
+        if let Some(session) = self.sessions.get_mut(&key(file)) {
+            session.confirmed_offset = confirmed_offset;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, file: &Path) -> Result<(), String> {
+        if self.sessions.remove(&key(file)).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(&self.sessions)
+            .map_err(|e| format!("Erreur lors de la sérialisation des sessions d'upload : {}", e))?;
+        write_atomic(&self.path, &content)
+    }
+}
+
+fn key(file: &Path) -> String {
+    file.to_string_lossy().into_owned()
+}